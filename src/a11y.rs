@@ -0,0 +1,97 @@
+// --- Accessibility: what screen readers are told as a race goes on ---
+//
+// Announcements go out two ways each frame: as an AccessKit live region,
+// which desktop screen readers pick up, and as an egui output event, which
+// the web build reads aloud when `Options::screen_reader` is on.
+use eframe::egui;
+use eframe::egui::accesskit;
+
+use crate::events::{GameEvent, GameEventHandler};
+
+/// WPM milestones are announced every this many words per minute
+pub const WPM_MILESTONE_STEP: u32 = 10;
+
+#[derive(Debug, Clone, Default)]
+pub struct Announcer {
+    /// The word last announced as the one being typed
+    current_word: Option<String>,
+    /// Words finished this race; milestones wait for the first so an early
+    /// burst of speed isn't announced
+    words: usize,
+    /// Highest WPM milestone announced this race
+    milestone: u32,
+    /// Announcements not yet sent, oldest first
+    pending: Vec<String>,
+    /// What the live region says now
+    last: String,
+}
+
+impl Announcer {
+    /// Forget the last race, ready for the next
+    pub fn reset(&mut self) {
+        *self = Announcer::default();
+    }
+
+    /// Announce `word` if it isn't the one already announced
+    pub fn set_current_word(&mut self, word: &str) {
+        if word.is_empty() || self.current_word.as_deref() == Some(word) {
+            return;
+        }
+        self.current_word = Some(word.to_string());
+        self.pending.push(word.to_string());
+    }
+
+    /// Announce the highest milestone `wpm` has passed, if it is a new high
+    pub fn observe_wpm(&mut self, wpm: f32) {
+        if self.words == 0 || !wpm.is_finite() || wpm <= 0.0 {
+            return;
+        }
+        let reached = wpm as u32 / WPM_MILESTONE_STEP * WPM_MILESTONE_STEP;
+        if reached > self.milestone {
+            self.milestone = reached;
+            self.pending.push(format!("{} words per minute", reached));
+        }
+    }
+
+    /// Announcements waiting to go out, oldest first
+    pub fn take(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Send this frame's announcements and keep the live region in place
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let pending = self.take();
+        if !pending.is_empty() {
+            self.last = pending.join(". ");
+            let info = egui::WidgetInfo::labeled(egui::WidgetType::Label, true, &self.last);
+            ctx.output_mut(|o| o.events.push(egui::output::OutputEvent::ValueChanged(info)));
+        }
+        ctx.accesskit_node_builder(egui::Id::new("announcer"), |node| {
+            node.set_role(accesskit::Role::Label);
+            node.set_live(accesskit::Live::Polite);
+            node.set_label(self.last.as_str());
+        });
+    }
+}
+
+impl GameEventHandler for Announcer {
+    fn handle_game_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::WordCompleted { .. } => self.words += 1,
+            GameEvent::ErrorMade { expected, .. } => {
+                let expected = if *expected == ' ' { "space".to_string() } else { expected.to_string() };
+                self.pending.push(format!("Error, expected {}", expected));
+            }
+            GameEvent::RaceFinished { wpm, accuracy } => {
+                self.pending.push(format!(
+                    "Race finished. {:.0} words per minute, {:.0} percent accuracy",
+                    wpm, accuracy
+                ));
+            }
+            GameEvent::GhostOvertook { player_ahead } => {
+                let text = if *player_ahead { "You took the lead" } else { "The ghost passed you" };
+                self.pending.push(text.to_string());
+            }
+        }
+    }
+}
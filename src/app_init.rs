@@ -1,31 +0,0 @@
-use crate::{
-    game::GameState,
-    keyboard::{input::TypingBuffer},
-    sections_top::TopSectionState,
-    sections_keyboard::KeyboardDisplayState,
-};
-use crate::sections::FooterState;
-// use crate::keyboard::KeyboardState; (removed, not found)
-use crate::ui::sections_left::PlayerViewState;
-use std::collections::HashMap;
-
-use crate::TyperRacerApp;
-use crate::keyboard::simulator::KeyboardSimulator;
-
-// fn init_keyboard_state() -> HashMap<String, KeyboardState> { (removed)
-
-
-
-
-
-
-
-
-
-
-
-    // for key in keys.iter() {
-    //     keyboard_state.insert(key.to_string(), KeyboardState::default());
-    // }
-    // keyboard_state
-
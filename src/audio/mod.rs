@@ -0,0 +1,134 @@
+// Sound effects: synthesized on the fly and played through the browser's
+// Web Audio API or, on desktop, the system's command-line audio player
+pub mod synth;
+pub mod output;
+
+pub use synth::*;
+pub use output::*;
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::{GameEvent, GameEventHandler};
+use crate::settings::Settings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Sound {
+    /// A key typed in a race
+    Click,
+    /// A wrong key
+    ErrorBuzz,
+    /// Taking the lead from the ghost
+    Whoosh,
+    /// Each second of the pre-race countdown
+    CountdownBeep,
+    /// Crossing the finish line
+    Fanfare,
+}
+
+impl Sound {
+    pub const ALL: [Sound; 5] = [
+        Sound::Click,
+        Sound::ErrorBuzz,
+        Sound::Whoosh,
+        Sound::CountdownBeep,
+        Sound::Fanfare,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Sound::Click => "Key clicks",
+            Sound::ErrorBuzz => "Error buzz",
+            Sound::Whoosh => "Overtake whoosh",
+            Sound::CountdownBeep => "Countdown beeps",
+            Sound::Fanfare => "Finish fanfare",
+        }
+    }
+}
+
+/// The sound a game event makes, if any
+pub fn sound_for(event: &GameEvent) -> Option<Sound> {
+    match event {
+        GameEvent::ErrorMade { .. } => Some(Sound::ErrorBuzz),
+        GameEvent::RaceFinished { .. } => Some(Sound::Fanfare),
+        GameEvent::GhostOvertook { player_ahead: true } => Some(Sound::Whoosh),
+        GameEvent::GhostOvertook { player_ahead: false } | GameEvent::WordCompleted { .. } => None,
+    }
+}
+
+/// Collects the sounds asked for each frame and plays them. The speaker is
+/// opened on first use, since browsers only allow audio after a key press.
+#[derive(Default)]
+pub struct Audio {
+    queued: Vec<Sound>,
+    /// Each sound rendered once, at full volume
+    rendered: BTreeMap<Sound, Vec<f32>>,
+    speaker: Option<Speaker>,
+    /// Opening or playing through the speaker failed; don't keep trying
+    unavailable: bool,
+}
+
+impl Audio {
+    pub fn queue(&mut self, sound: Sound) {
+        self.queued.push(sound);
+    }
+
+    /// This frame's sounds that settings allow, each once. A click is
+    /// dropped when the same key also buzzed.
+    pub fn take_frame(&mut self, settings: &Settings) -> Vec<Sound> {
+        let mut sounds = std::mem::take(&mut self.queued);
+        if !settings.sound || settings.volume <= 0.0 {
+            return Vec::new();
+        }
+        if sounds.contains(&Sound::ErrorBuzz) {
+            sounds.retain(|s| *s != Sound::Click);
+        }
+        sounds.sort();
+        sounds.dedup();
+        sounds.retain(|s| !settings.muted_sounds.contains(s));
+        sounds
+    }
+
+    /// Open the speaker if it isn't already, unless that has failed before.
+    /// Returns whether sound can be played.
+    pub fn open_speaker(&mut self) -> bool {
+        if self.speaker.is_none() && !self.unavailable {
+            self.speaker = Speaker::open();
+            self.unavailable = self.speaker.is_none();
+        }
+        self.speaker.is_some()
+    }
+
+    /// Why nothing can be heard, once opening or playing has failed
+    pub fn problem(&self) -> Option<&'static str> {
+        self.unavailable.then_some(UNAVAILABLE)
+    }
+
+    /// Play this frame's sounds at the volume in `settings`
+    pub fn flush(&mut self, settings: &Settings) {
+        let sounds = self.take_frame(settings);
+        if sounds.is_empty() || !self.open_speaker() {
+            return;
+        }
+        let Some(speaker) = self.speaker.as_mut() else {
+            return;
+        };
+        for sound in sounds {
+            let samples = self.rendered.entry(sound).or_insert_with(|| render(sound, SAMPLE_RATE));
+            if !speaker.play(samples.iter().map(|s| s * settings.volume).collect()) {
+                self.speaker = None;
+                self.unavailable = true;
+                return;
+            }
+        }
+    }
+}
+
+impl GameEventHandler for Audio {
+    fn handle_game_event(&mut self, event: &GameEvent) {
+        if let Some(sound) = sound_for(event) {
+            self.queue(sound);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// Where rendered samples are played. Browser builds hand each sound to Web
+// Audio; native builds mix them into the default output device through cpal
+// (WASAPI, Core Audio or ALSA).
+pub use backend::{Speaker, UNAVAILABLE};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::sync::Arc;
+
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::{FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
+
+    use crate::audio::synth::SAMPLE_RATE;
+
+    /// Shown in settings when no output device could be opened
+    pub const UNAVAILABLE: &str = "No sound: no audio output device was found";
+
+    pub struct Speaker {
+        sounds: Sender<Vec<f32>>,
+        /// Set by the stream when the device goes away
+        failed: Arc<AtomicBool>,
+        /// Plays for as long as it is kept
+        _stream: Stream,
+    }
+
+    impl Speaker {
+        /// Open the default output device and start mixing sounds into it.
+        /// None when there is no device or it can't be opened.
+        pub fn open() -> Option<Speaker> {
+            let device = cpal::default_host().default_output_device()?;
+            let supported = device.default_output_config().ok()?;
+            let format = supported.sample_format();
+            let config: StreamConfig = supported.into();
+            let (sounds, incoming) = channel::<Vec<f32>>();
+            let mixer = Mixer {
+                incoming,
+                voices: Vec::new(),
+                step: SAMPLE_RATE as f64 / config.sample_rate.0 as f64,
+            };
+            let failed = Arc::new(AtomicBool::new(false));
+            let stream = match format {
+                SampleFormat::F32 => build::<f32>(&device, &config, mixer, failed.clone()),
+                SampleFormat::I16 => build::<i16>(&device, &config, mixer, failed.clone()),
+                SampleFormat::U16 => build::<u16>(&device, &config, mixer, failed.clone()),
+                SampleFormat::I32 => build::<i32>(&device, &config, mixer, failed.clone()),
+                _ => None,
+            }?;
+            stream.play().ok()?;
+            Some(Speaker { sounds, failed, _stream: stream })
+        }
+
+        /// Start playing `samples` over whatever is already playing. False
+        /// once the device has gone away.
+        pub fn play(&mut self, samples: Vec<f32>) -> bool {
+            !self.failed.load(Ordering::Relaxed) && self.sounds.send(samples).is_ok()
+        }
+    }
+
+    /// Sounds handed over by `Speaker::play`, mixed on the audio thread
+    struct Mixer {
+        incoming: Receiver<Vec<f32>>,
+        /// Each playing sound and how far into it playback is
+        voices: Vec<(Vec<f32>, f64)>,
+        /// Sound samples per device sample, as sounds are rendered at `SAMPLE_RATE`
+        step: f64,
+    }
+
+    impl Mixer {
+        fn receive(&mut self) {
+            self.voices.extend(self.incoming.try_iter().map(|samples| (samples, 0.0)));
+        }
+
+        /// The next device sample: every playing sound added together
+        fn next_sample(&mut self) -> f32 {
+            let mut mixed = 0.0;
+            for (samples, position) in self.voices.iter_mut() {
+                let index = *position as usize;
+                if let Some(&sample) = samples.get(index) {
+                    let next = samples.get(index + 1).copied().unwrap_or(0.0);
+                    let fraction = (*position - index as f64) as f32;
+                    mixed += sample + (next - sample) * fraction;
+                }
+                *position += self.step;
+            }
+            self.voices.retain(|(samples, position)| (*position as usize) < samples.len());
+            mixed.clamp(-1.0, 1.0)
+        }
+    }
+
+    fn build<T: SizedSample + FromSample<f32>>(
+        device: &cpal::Device,
+        config: &StreamConfig,
+        mut mixer: Mixer,
+        failed: Arc<AtomicBool>,
+    ) -> Option<Stream> {
+        let channels = config.channels as usize;
+        device
+            .build_output_stream(
+                config,
+                move |data: &mut [T], _| {
+                    mixer.receive();
+                    for frame in data.chunks_mut(channels) {
+                        frame.fill(T::from_sample(mixer.next_sample()));
+                    }
+                },
+                move |_| failed.store(true, Ordering::Relaxed),
+                None,
+            )
+            .ok()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use web_sys::AudioContext;
+
+    use crate::audio::synth::SAMPLE_RATE;
+
+    /// Shown in settings when the browser refuses an audio context
+    pub const UNAVAILABLE: &str = "No sound: this browser has no audio output";
+
+    pub struct Speaker {
+        context: AudioContext,
+    }
+
+    impl Speaker {
+        pub fn open() -> Option<Speaker> {
+            AudioContext::new().ok().map(|context| Speaker { context })
+        }
+
+        /// Start playing `samples` over whatever is already playing
+        pub fn play(&mut self, samples: Vec<f32>) -> bool {
+            // Contexts made before the page was interacted with start suspended
+            let _ = self.context.resume();
+            let Ok(buffer) = self.context.create_buffer(1, samples.len() as u32, SAMPLE_RATE as f32) else {
+                return false;
+            };
+            if buffer.copy_to_channel(&samples, 0).is_err() {
+                return false;
+            }
+            let Ok(source) = self.context.create_buffer_source() else {
+                return false;
+            };
+            source.set_buffer(Some(&buffer));
+            source.connect_with_audio_node(&self.context.destination()).is_ok() && source.start().is_ok()
+        }
+    }
+}
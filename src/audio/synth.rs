@@ -0,0 +1,76 @@
+// Builds every sound effect from oscillators and noise, so the game ships
+// no audio files. Output is mono f32 samples in -1.0..=1.0.
+use std::f32::consts::TAU;
+
+use crate::audio::Sound;
+use crate::rng::SeededRng;
+
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// C major arpeggio the fanfare climbs, as (frequency, seconds)
+const FANFARE: [(f32, f32); 4] = [(523.25, 0.12), (659.25, 0.12), (783.99, 0.12), (1046.5, 0.45)];
+
+/// How long `sound` lasts
+pub fn duration_secs(sound: Sound) -> f32 {
+    match sound {
+        Sound::Click => 0.018,
+        Sound::ErrorBuzz => 0.14,
+        Sound::Whoosh => 0.4,
+        Sound::CountdownBeep => 0.15,
+        Sound::Fanfare => FANFARE.iter().map(|(_, secs)| secs).sum(),
+    }
+}
+
+/// Render `sound` at `sample_rate`. The same sound always renders the same.
+pub fn render(sound: Sound, sample_rate: u32) -> Vec<f32> {
+    let rate = sample_rate as f32;
+    let secs = duration_secs(sound);
+    let mut noise = SeededRng::from_seed(sound.label());
+    let samples = match sound {
+        Sound::Click => tone(secs, rate, |t| {
+            let white = noise.next_f32() * 2.0 - 1.0;
+            0.6 * white * (-t / 0.002).exp() + 0.3 * (TAU * 1800.0 * t).sin() * (-t / 0.004).exp()
+        }),
+        Sound::ErrorBuzz => tone(secs, rate, |t| {
+            // Two detuned square waves beat against each other
+            let square = |freq: f32| (TAU * freq * t).sin().signum();
+            0.15 * (square(110.0) + square(117.0))
+        }),
+        Sound::Whoosh => {
+            let mut low = 0.0;
+            tone(secs, rate, |t| {
+                // Noise through a low-pass filter that opens and closes again
+                let swell = (std::f32::consts::PI * t / secs).sin();
+                let cutoff = 300.0 + 3000.0 * swell;
+                low += (1.0 - (-TAU * cutoff / rate).exp()) * (noise.next_f32() * 2.0 - 1.0 - low);
+                1.6 * low * swell
+            })
+        }
+        Sound::CountdownBeep => tone(secs, rate, |t| 0.4 * (TAU * 880.0 * t).sin()),
+        Sound::Fanfare => FANFARE
+            .iter()
+            .flat_map(|&(freq, secs)| {
+                tone(secs, rate, |t| {
+                    let phase = TAU * freq * t;
+                    0.35 * (phase.sin() + 0.3 * (2.0 * phase).sin() + 0.15 * (3.0 * phase).sin())
+                })
+            })
+            .collect(),
+    };
+    samples.into_iter().map(|s| s.clamp(-1.0, 1.0)).collect()
+}
+
+/// `secs` of `wave(t)`, faded in and out so it starts and ends at silence
+/// instead of with a pop
+fn tone(secs: f32, rate: f32, mut wave: impl FnMut(f32) -> f32) -> Vec<f32> {
+    let len = (secs * rate).round() as usize;
+    let attack = (0.002 * rate).max(1.0);
+    let release = (0.2 * len as f32).max(1.0);
+    (0..len)
+        .map(|i| {
+            let fade_in = (i as f32 / attack).min(1.0);
+            let fade_out = ((len - 1 - i) as f32 / release).min(1.0);
+            wave(i as f32 / rate) * fade_in * fade_out
+        })
+        .collect()
+}
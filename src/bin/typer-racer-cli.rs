@@ -0,0 +1,239 @@
+// Headless tools for saved runs: list replays, print their stats, diff two
+// runs of the same race, upgrade old replay files and tally key analytics.
+//
+//     cargo run --bin typer-racer-cli -- stats ada-1747078025974
+//     cargo run --bin typer-racer-cli -- keys ~/.local/share/typer-racer/replays
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::io::Write;
+    use std::path::Path;
+
+    use typer_racer::game::{MeditationQuote, Replay, BACKSPACE};
+    use typer_racer::inspect::{diff_replays, summarize_replay};
+    use typer_racer::keyboard::replay::{
+        is_bare_replay, list_replays, load_replay_named, parse_replay, GHOST_PREFIX, REPLAYS_NAMESPACE,
+    };
+    use typer_racer::quote_loader::{load_expanded_meditations_from, DEFAULT_QUOTES_PATH};
+    use typer_racer::stats::{analyze_replay, KeyStats};
+    use typer_racer::storage::{data_dir, FileStorage};
+
+    const USAGE: &str = "usage: typer-racer-cli [--data DIR] [--quotes FILE] COMMAND
+commands:
+  list                  saved replays with their scores
+  stats REPLAY...       stats for each run
+  diff A B              two runs of the same race, character by character
+  convert IN [OUT]      rewrite an old bare-array replay in the current format
+  keys [DIR]            per-key analytics over every replay in a directory,
+                        saved replays by default
+REPLAY is a file path or the name of a saved replay";
+
+    /// Print a line, stopping quietly if the reader went away (e.g. `| head`)
+    macro_rules! out {
+        ($($arg:tt)*) => {
+            if writeln!(std::io::stdout(), $($arg)*).is_err() {
+                std::process::exit(0);
+            }
+        };
+    }
+
+    /// Rows shown by `keys`
+    const KEY_ROWS: usize = 15;
+
+    fn fail(message: &str) -> ! {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+
+    struct Context {
+        storage: FileStorage,
+        quotes: Vec<MeditationQuote>,
+    }
+
+    impl Context {
+        /// A replay from a file path, or else from saved replays by name
+        fn replay(&self, name: &str) -> Replay {
+            if Path::new(name).is_file() {
+                return read_replay(Path::new(name)).unwrap_or_else(|| fail(&format!("{} is not a replay", name)));
+            }
+            let replay = load_replay_named(&self.storage, name);
+            if replay.events.is_empty() {
+                fail(&format!("no replay file or saved replay called {}", name));
+            }
+            replay
+        }
+    }
+
+    fn read_replay(path: &Path) -> Option<Replay> {
+        std::fs::read_to_string(path).ok().and_then(|json| parse_replay(&json))
+    }
+
+    fn print_stats(ctx: &Context, name: &str, replay: &Replay) {
+        let summary = summarize_replay(&ctx.quotes, replay);
+        out!("{}", name);
+        out!("  race      quote {} / {} / seed {:?}", replay.config.quote_index, replay.config.mode.label(), replay.config.seed);
+        out!("  wpm       {:.1}", summary.wpm);
+        out!("  accuracy  {:.1}%", summary.accuracy);
+        out!("  time      {:.1}s{}", summary.elapsed, if summary.finished { "" } else { " (unfinished)" });
+        out!("  keys      {} ({} errors, {} backspaces)", summary.keystrokes, summary.errors, summary.backspaces);
+        let stats = analyze_replay(&ctx.quotes, replay);
+        let weakest: String = stats.weakest_keys(5).into_iter().map(show_key).collect::<Vec<_>>().join(" ");
+        if !weakest.is_empty() {
+            out!("  weakest   {}", weakest);
+        }
+    }
+
+    fn show_key(c: char) -> String {
+        match c {
+            ' ' => "space".to_string(),
+            BACKSPACE => "backspace".to_string(),
+            c => c.to_string(),
+        }
+    }
+
+    fn list(ctx: &Context) {
+        let names = list_replays(&ctx.storage);
+        if names.is_empty() {
+            out!("no saved replays in {}", ctx.storage.root.display());
+        }
+        for name in names {
+            let replay = load_replay_named(&ctx.storage, &name);
+            let summary = summarize_replay(&ctx.quotes, &replay);
+            out!(
+                "{:<32} {:<16} quote {:<4} {:>6.1} wpm {:>6.1}%",
+                name,
+                replay.config.mode.label(),
+                replay.config.quote_index,
+                summary.wpm,
+                summary.accuracy
+            );
+        }
+    }
+
+    fn diff(ctx: &Context, a_name: &str, b_name: &str) {
+        let (a, b) = (ctx.replay(a_name), ctx.replay(b_name));
+        let Some(chars) = diff_replays(&ctx.quotes, &a, &b) else {
+            fail("the replays are of different races");
+        };
+        out!("{:>5}  char  {:>8}  {:>8}  {:>7}", "pos", "A ms", "B ms", "B - A");
+        let show = |ms: Option<u64>, missed: bool| match ms {
+            Some(ms) => format!("{}{}", ms, if missed { "!" } else { "" }),
+            None => "-".to_string(),
+        };
+        for c in &chars {
+            let delta = c.delta_ms().map(|d| format!("{:+}", d)).unwrap_or_default();
+            out!(
+                "{:>5}  {:<4}  {:>8}  {:>8}  {:>7}",
+                c.index,
+                show_key(c.expected),
+                show(c.a_ms, c.a_missed),
+                show(c.b_ms, c.b_missed),
+                delta
+            );
+        }
+        let (sa, sb) = (summarize_replay(&ctx.quotes, &a), summarize_replay(&ctx.quotes, &b));
+        out!("A {:.1} wpm {:.1}%  B {:.1} wpm {:.1}%  (! = mistyped)", sa.wpm, sa.accuracy, sb.wpm, sb.accuracy);
+    }
+
+    fn convert(input: &str, output: Option<&str>) {
+        let json = std::fs::read_to_string(input).unwrap_or_else(|e| fail(&format!("could not read {}: {}", input, e)));
+        let replay = parse_replay(&json).unwrap_or_else(|| fail(&format!("{} is not a replay", input)));
+        if !is_bare_replay(&json) && output.is_none() {
+            out!("{} is already in the current format", input);
+            return;
+        }
+        let upgraded = serde_json::to_string_pretty(&replay).unwrap_or_else(|e| fail(&e.to_string()));
+        let output = output.unwrap_or(input);
+        if let Err(e) = std::fs::write(output, upgraded) {
+            fail(&format!("could not write {}: {}", output, e));
+        }
+        out!("wrote {} ({} keys, quote {})", output, replay.events.len(), replay.config.quote_index);
+    }
+
+    fn keys(ctx: &Context, dir: &str) {
+        let entries = std::fs::read_dir(dir).unwrap_or_else(|e| fail(&format!("could not read {}: {}", dir, e)));
+        let mut paths: Vec<_> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter(|p| !p.file_name().is_some_and(|name| name.to_string_lossy().starts_with(GHOST_PREFIX)))
+            .collect();
+        paths.sort();
+        let mut stats = KeyStats::default();
+        let mut runs = 0;
+        for path in &paths {
+            if let Some(replay) = read_replay(path) {
+                stats.merge(&analyze_replay(&ctx.quotes, &replay));
+                runs += 1;
+            }
+        }
+        out!("{} replays, {:.0} ms per key overall", runs, stats.overall_average_ms().unwrap_or(0.0));
+        out!("{:<10} {:>7} {:>7} {:>8} {:>8}", "key", "hits", "misses", "error %", "avg ms");
+        let mut ranked: Vec<(&char, _)> = stats.keys.iter().collect();
+        ranked.sort_by(|a, b| stats.weakness(b.1).total_cmp(&stats.weakness(a.1)));
+        for (key, stat) in ranked.into_iter().take(KEY_ROWS) {
+            out!(
+                "{:<10} {:>7} {:>7} {:>8.1} {:>8}",
+                show_key(*key),
+                stat.hits,
+                stat.misses,
+                stat.error_rate() * 100.0,
+                stat.average_ms().map(|ms| format!("{:.0}", ms)).unwrap_or_else(|| "-".to_string())
+            );
+        }
+        let bigrams = stats.weakest_bigrams(5);
+        if !bigrams.is_empty() {
+            out!("weakest bigrams: {}", bigrams.join(" "));
+        }
+    }
+
+    pub fn main() {
+        let mut data = data_dir();
+        let mut quotes_path = DEFAULT_QUOTES_PATH.to_string();
+        let mut words = Vec::new();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--data" => data = args.next().unwrap_or_else(|| fail(USAGE)).into(),
+                "--quotes" => quotes_path = args.next().unwrap_or_else(|| fail(USAGE)),
+                "-h" | "--help" => {
+                    out!("{}", USAGE);
+                    return;
+                }
+                _ => words.push(arg),
+            }
+        }
+        let ctx = Context {
+            storage: FileStorage::new(data),
+            quotes: load_expanded_meditations_from(&quotes_path),
+        };
+        if ctx.quotes.is_empty() && words.first().is_some_and(|w| w != "convert") {
+            fail(&format!("no passages found in {}", quotes_path));
+        }
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match words.as_slice() {
+            ["list"] => list(&ctx),
+            ["stats", names @ ..] if !names.is_empty() => {
+                for name in names {
+                    print_stats(&ctx, name, &ctx.replay(name));
+                }
+            }
+            ["diff", a, b] => diff(&ctx, a, b),
+            ["convert", input] => convert(input, None),
+            ["convert", input, output] => convert(input, Some(output)),
+            ["keys"] => {
+                let dir = ctx.storage.root.join(REPLAYS_NAMESPACE);
+                keys(&ctx, &dir.to_string_lossy())
+            }
+            ["keys", dir] => keys(&ctx, dir),
+            _ => fail(USAGE),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    native::main();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,148 @@
+// Reference race server: one lobby, JSON messages over WebSocket.
+//
+//     cargo run --bin typer-racer-server -- --addr 127.0.0.1:9001 --max-players 8
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::collections::BTreeMap;
+    use std::io::ErrorKind;
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc::{channel, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use tungstenite::Message;
+    use typer_racer::net::{
+        decode, encode, ClientMessage, Outbox, PlayerId, RaceServer, Recipient, ServerMessage,
+        DEFAULT_MAX_PLAYERS, DEFAULT_PORT,
+    };
+    use typer_racer::quote_loader::load_expanded_meditations;
+
+    /// The lobby plus a way to reach every connected player
+    struct Hub {
+        server: RaceServer,
+        senders: BTreeMap<PlayerId, Sender<String>>,
+    }
+
+    impl Hub {
+        fn dispatch(&self, outbox: Outbox) {
+            for (recipient, message) in outbox {
+                let text = encode(&message);
+                match recipient {
+                    Recipient::All => {
+                        for sender in self.senders.values() {
+                            let _ = sender.send(text.clone());
+                        }
+                    }
+                    Recipient::Player(id) => {
+                        if let Some(sender) = self.senders.get(&id) {
+                            let _ = sender.send(text);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn serve(stream: TcpStream, hub: Arc<Mutex<Hub>>) {
+        let Ok(mut socket) = tungstenite::accept(stream) else {
+            return;
+        };
+        let joined = hub.lock().ok().and_then(|mut hub| hub.server.connect());
+        let Some(id) = joined else {
+            let full = ServerMessage::Error { message: "The lobby is full".to_string() };
+            let _ = socket.send(Message::text(encode(&full)));
+            let _ = socket.close(None);
+            return;
+        };
+        let (sender, outgoing) = channel();
+        if let Ok(mut hub) = hub.lock() {
+            hub.senders.insert(id, sender);
+        }
+        let _ = socket.get_ref().set_read_timeout(Some(Duration::from_millis(10)));
+        log(&format!("player {} connected", id));
+        'connection: loop {
+            for text in outgoing.try_iter() {
+                if socket.send(Message::text(text)).is_err() {
+                    break 'connection;
+                }
+            }
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    let Some(message) = decode::<ClientMessage>(&text) else {
+                        continue;
+                    };
+                    if let Ok(mut hub) = hub.lock() {
+                        let now_ms = Utc::now().timestamp_millis() as u64;
+                        let outbox = hub.server.handle(id, message, now_ms);
+                        hub.dispatch(outbox);
+                    }
+                }
+                Ok(Message::Close(_)) => break,
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(_) => break,
+            }
+        }
+        if let Ok(mut hub) = hub.lock() {
+            hub.senders.remove(&id);
+            let outbox = hub.server.disconnect(id);
+            hub.dispatch(outbox);
+        }
+        log(&format!("player {} left", id));
+    }
+
+    fn log(message: &str) {
+        println!("[{}] {}", Utc::now().format("%H:%M:%S"), message);
+    }
+
+    pub fn main() {
+        let mut addr = format!("127.0.0.1:{}", DEFAULT_PORT);
+        let mut max_players = DEFAULT_MAX_PLAYERS;
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--addr" => addr = args.next().unwrap_or(addr),
+                "--max-players" => {
+                    max_players = args.next().and_then(|n| n.parse().ok()).unwrap_or(max_players)
+                }
+                _ => {
+                    eprintln!("usage: typer-racer-server [--addr HOST:PORT] [--max-players N]");
+                    std::process::exit(2);
+                }
+            }
+        }
+        let quotes = load_expanded_meditations();
+        let listener = match TcpListener::bind(&addr) {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("could not listen on {}: {}", addr, e);
+                std::process::exit(1);
+            }
+        };
+        log(&format!(
+            "racing {} quotes for up to {} players on ws://{}",
+            quotes.len(),
+            max_players,
+            addr
+        ));
+        let hub = Arc::new(Mutex::new(Hub {
+            server: RaceServer::new(quotes.len(), max_players),
+            senders: BTreeMap::new(),
+        }));
+        for stream in listener.incoming().flatten() {
+            let hub = hub.clone();
+            std::thread::spawn(move || serve(stream, hub));
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    native::main();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,372 @@
+// Terminal frontend for playing over SSH, sharing the game core with the
+// window app. Build it with the `tui` feature:
+//
+//     cargo run --features tui --bin typer-racer-tui -- --ghost
+
+use std::io::{self, Stdout, Write};
+use std::time::Duration;
+
+use chrono::Utc;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use typer_racer::game::{chars_match, quote_for_seed};
+use typer_racer::keyboard::replay::{load_replay, save_replay};
+use typer_racer::keyboard::simulator::KeyboardSimulator;
+use typer_racer::keyboard::event::KeyboardEvent;
+use typer_racer::quote_loader::load_expanded_meditations;
+use typer_racer::storage::{default_storage, Storage};
+use typer_racer::time::{Clock, ScaledClock, SystemClock};
+use typer_racer::{GameMode, GameState, GameStatus, MeditationQuote, RaceConfig, Replay};
+
+const USAGE: &str = "usage: typer-racer-tui [--quote N] [--seconds N] [--ghost] [--speed X]
+  --quote N     race passage N instead of a random one
+  --seconds N   a timed run of N seconds
+  --ghost       race your last saved run
+  --speed X     play the ghost back X times as fast, e.g. 0.5 for slow motion";
+const FRAME: Duration = Duration::from_millis(33);
+/// Passage lines shown around the caret
+const PASSAGE_LINES: usize = 8;
+
+/// Restores the terminal however the program exits
+struct Screen {
+    out: Stdout,
+}
+
+impl Screen {
+    fn open() -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(Self { out })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+enum Key {
+    Char(char),
+    Backspace,
+    Enter,
+    Quit,
+}
+
+/// Keys pressed since the last frame, waiting up to one frame for the first
+fn read_keys() -> io::Result<Vec<Key>> {
+    let mut keys = Vec::new();
+    let mut wait = FRAME;
+    while event::poll(wait)? {
+        wait = Duration::ZERO;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        keys.push(match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Key::Quit,
+            KeyCode::Esc => Key::Quit,
+            KeyCode::Char(c) => Key::Char(c),
+            KeyCode::Backspace => Key::Backspace,
+            KeyCode::Enter => Key::Enter,
+            _ => continue,
+        });
+    }
+    Ok(keys)
+}
+
+struct Tui {
+    game: GameState,
+    ghost: GameState,
+    simulator: KeyboardSimulator,
+    storage: Box<dyn Storage>,
+    clock: Box<dyn Clock>,
+    /// Drives the ghost, which may run at its own speed
+    ghost_clock: Box<dyn Clock>,
+    mode: GameMode,
+    quote: Option<usize>,
+}
+
+impl Tui {
+    fn new(quotes: Vec<MeditationQuote>, mode: GameMode, quote: Option<usize>, ghost_clock: Box<dyn Clock>) -> Self {
+        Self {
+            game: GameState::new(quotes.clone()),
+            ghost: GameState { status: GameStatus::NotStarted, ..GameState::new(quotes) },
+            simulator: KeyboardSimulator::new(),
+            storage: default_storage(),
+            clock: Box::new(SystemClock),
+            ghost_clock,
+            mode,
+            quote,
+        }
+    }
+
+    fn new_race(&mut self) {
+        let seed = Utc::now().timestamp_millis().to_string();
+        let quote_index = self.quote.unwrap_or_else(|| quote_for_seed(&seed, self.game.quotes.len()));
+        let config = RaceConfig { quote_index, seed, mode: self.mode, ..RaceConfig::default() };
+        self.start(&config, None);
+    }
+
+    /// Race a replay's ghost on the race it was recorded on
+    fn race_replay(&mut self, replay: Replay) {
+        if replay.events.is_empty() || replay.config.seed.is_empty() {
+            self.new_race();
+            return;
+        }
+        let config = replay.config.clone();
+        self.start(&config, Some(replay));
+    }
+
+    fn start(&mut self, config: &RaceConfig, ghost: Option<Replay>) {
+        let now = self.clock.now_ms();
+        self.game.start_race_at(config, now);
+        self.simulator.stop();
+        self.ghost.status = GameStatus::NotStarted;
+        if let Some(replay) = ghost.filter(|_| config.mode.allows_ghost()) {
+            let ghost_now = self.ghost_clock.now_ms();
+            self.ghost.start_race_at(config, ghost_now);
+            self.simulator.start_replay(replay.active_events(), self.ghost_clock.as_ref());
+        }
+    }
+
+    fn has_ghost(&self) -> bool {
+        self.ghost.status != GameStatus::NotStarted
+    }
+
+    /// Type this frame's keys and advance the clocks and the ghost, saving
+    /// the replay when the race ends
+    fn tick(&mut self, keys: &[Key]) {
+        if self.game.status != GameStatus::Running {
+            return;
+        }
+        let now = self.clock.now_ms();
+        self.game.tick(now);
+        for key in keys {
+            match key {
+                Key::Char(c) => {
+                    self.game.type_char(*c, now);
+                }
+                Key::Backspace => {
+                    self.game.backspace(now);
+                }
+                _ => {}
+            }
+        }
+        let ghost_now = self.ghost_clock.now_ms();
+        while let Some(KeyboardEvent::KeyPress { key, .. }) = self.simulator.tick(self.ghost_clock.as_ref()) {
+            if key == "BACKSPACE" {
+                self.ghost.backspace(ghost_now);
+            } else if let Some(c) = key.chars().next() {
+                self.ghost.type_char(c, ghost_now);
+            }
+        }
+        self.ghost.tick(ghost_now);
+        if self.game.status != GameStatus::Finished {
+            return;
+        }
+        // Timed runs already stopped the clock exactly at their limit
+        if self.game.mode.time_limit().is_none() {
+            self.game.elapsed = self.game.elapsed_at(now);
+        }
+        self.simulator.stop();
+        let replay = self.game.recorded_replay();
+        save_replay(self.storage.as_mut(), &replay);
+    }
+
+    fn draw(&self, out: &mut Stdout) -> io::Result<()> {
+        let (width, _) = terminal::size()?;
+        let width = (width as usize).clamp(20, 100);
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+        match self.game.status {
+            GameStatus::Running => self.draw_race(out, width)?,
+            _ => self.draw_results(out)?,
+        }
+        out.flush()
+    }
+
+    fn draw_race(&self, out: &mut Stdout, width: usize) -> io::Result<()> {
+        let header = format!(
+            "Typer Racer  {}  {}  {:>5.1} WPM  {:>5.1}%",
+            self.game.mode.label(),
+            clock(self.game.elapsed),
+            self.game.wpm(),
+            self.game.accuracy()
+        );
+        line(out, 0, &header)?;
+        let road = "=".repeat(width);
+        queue!(out, MoveTo(0, 2), SetForegroundColor(Color::DarkGrey), Print(&road), ResetColor)?;
+        draw_lane(out, 3, "You", self.game.progress(), width, Color::Cyan)?;
+        let mut row = 4;
+        if self.has_ghost() {
+            let label = format!("Ghost {:.0}", self.ghost.wpm());
+            draw_lane(out, row, &label, self.ghost.progress(), width, Color::Magenta)?;
+            row += 1;
+        }
+        queue!(out, MoveTo(0, row), SetForegroundColor(Color::DarkGrey), Print(&road), ResetColor)?;
+        self.draw_passage(out, row + 2, width)?;
+        line(out, row + 3 + PASSAGE_LINES as u16, "Esc quits")
+    }
+
+    /// The passage wrapped to the terminal, coloured by what has been typed
+    fn draw_passage(&self, out: &mut Stdout, top: u16, width: usize) -> io::Result<()> {
+        let text: Vec<char> = self.game.target_text().chars().collect();
+        let typed: Vec<char> = self.game.input_buffer.chars().collect();
+        let lines = wrap(&text, width);
+        let caret_line = lines.iter().position(|&(start, end)| self.game.current_char < end.max(start + 1)).unwrap_or(0);
+        let first = caret_line.saturating_sub(1);
+        for (row, &(start, end)) in lines.iter().skip(first).take(PASSAGE_LINES).enumerate() {
+            queue!(out, MoveTo(0, top + row as u16))?;
+            for (i, &expected) in text.iter().enumerate().take(end).skip(start) {
+                let (color, attribute) = match typed.get(i) {
+                    Some(&c) if chars_match(expected, c) => (Color::Green, Attribute::Reset),
+                    Some(_) => (Color::Red, Attribute::Underlined),
+                    None if i == self.game.current_char => (Color::White, Attribute::Reverse),
+                    None => (Color::DarkGrey, Attribute::Reset),
+                };
+                queue!(out, SetForegroundColor(color), SetAttribute(attribute), Print(expected))?;
+                queue!(out, SetAttribute(Attribute::Reset))?;
+            }
+        }
+        queue!(out, ResetColor)
+    }
+
+    fn draw_results(&self, out: &mut Stdout) -> io::Result<()> {
+        let game = &self.game;
+        let mut lines = vec![
+            "Race finished".to_string(),
+            String::new(),
+            format!("WPM       {:.1}", game.wpm()),
+            format!("Accuracy  {:.1}%", game.accuracy()),
+            format!("Errors    {}", game.errors),
+            format!("Time      {}", clock(game.elapsed)),
+        ];
+        if self.has_ghost() {
+            let verdict = if self.ghost.status != GameStatus::Finished || game.elapsed < self.ghost.elapsed {
+                "You beat your ghost"
+            } else {
+                "Your ghost won"
+            };
+            lines.push(format!("Ghost     {:.1} WPM - {}", self.ghost.wpm(), verdict));
+        }
+        lines.push(String::new());
+        lines.push("Enter: new race   g: race this run's ghost   Esc: quit".to_string());
+        for (row, text) in lines.iter().enumerate() {
+            line(out, row as u16, text)?;
+        }
+        Ok(())
+    }
+}
+
+fn draw_lane(out: &mut Stdout, row: u16, label: &str, progress: f32, width: usize, color: Color) -> io::Result<()> {
+    let label = format!("{:<10}", label);
+    let track = width.saturating_sub(label.len() + 2).max(1);
+    let at = (progress.clamp(0.0, 1.0) * (track - 1) as f32).round() as usize;
+    let lane: String = (0..track).map(|i| if i == at { '>' } else { '.' }).collect();
+    queue!(out, MoveTo(0, row), Print(&label), SetForegroundColor(color), Print(lane), ResetColor, Print(" |"))
+}
+
+fn line(out: &mut Stdout, row: u16, text: &str) -> io::Result<()> {
+    queue!(out, MoveTo(0, row), Print(text))
+}
+
+/// Start and end of each wrapped line, breaking after spaces where possible
+fn wrap(text: &[char], width: usize) -> Vec<(usize, usize)> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let mut end = (start + width).min(text.len());
+        if end < text.len() {
+            if let Some(space) = text[start..end].iter().rposition(|&c| c == ' ') {
+                end = start + space + 1;
+            }
+        }
+        lines.push((start, end));
+        start = end;
+    }
+    lines
+}
+
+fn clock(seconds: f32) -> String {
+    format!("{:02}:{:04.1}", (seconds / 60.0) as u32, seconds % 60.0)
+}
+
+fn run(tui: &mut Tui, ghost: bool) -> io::Result<()> {
+    if ghost {
+        tui.race_replay(load_replay(tui.storage.as_ref()));
+    } else {
+        tui.new_race();
+    }
+    let mut screen = Screen::open()?;
+    loop {
+        let keys = read_keys()?;
+        if keys.iter().any(|k| matches!(k, Key::Quit)) {
+            return Ok(());
+        }
+        if tui.game.status == GameStatus::Finished {
+            for key in &keys {
+                match key {
+                    Key::Enter => tui.new_race(),
+                    Key::Char('g') => {
+                        tui.race_replay(tui.game.recorded_replay());
+                    }
+                    Key::Char('q') => return Ok(()),
+                    _ => continue,
+                }
+                break;
+            }
+        } else {
+            tui.tick(&keys);
+        }
+        tui.draw(&mut screen.out)?;
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut quote = None;
+    let mut mode = GameMode::Race;
+    let mut ghost = false;
+    let mut speed = 1.0;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--quote" => quote = Some(args.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| usage())),
+            "--seconds" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(seconds) => mode = GameMode::Timed { seconds },
+                None => usage(),
+            },
+            "--ghost" => ghost = true,
+            "--speed" => speed = args.next().and_then(|n| n.parse().ok()).filter(|x: &f64| *x > 0.0).unwrap_or_else(|| usage()),
+            _ => usage(),
+        }
+    }
+    let quotes = load_expanded_meditations();
+    if quotes.is_empty() {
+        eprintln!("no passages found; run from the directory holding level/");
+        std::process::exit(1);
+    }
+    let quote = quote.map(|q: usize| q.min(quotes.len() - 1));
+    let ghost_clock: Box<dyn Clock> = if speed == 1.0 {
+        Box::new(SystemClock)
+    } else {
+        Box::new(ScaledClock::new(SystemClock, speed))
+    };
+    let mut tui = Tui::new(quotes, mode, quote, ghost_clock);
+    if let Err(e) = run(&mut tui, ghost) {
+        eprintln!("terminal error: {}", e);
+        std::process::exit(1);
+    }
+}
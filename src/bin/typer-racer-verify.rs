@@ -0,0 +1,98 @@
+// Checks a submitted replay.json against the race it was submitted for:
+// recomputes its score and flags timing a person could not have produced.
+// Exits 0 when the replay looks genuine, 1 when it is flagged and 2 when it
+// cannot be read.
+//
+//     cargo run --bin typer-racer-verify -- replay.json --quote 3 --seed 1700000000 --wpm 92.5
+//
+// `--mode` is `race` (the default), `practice`, `timed:SECONDS` or `words:COUNT`.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use typer_racer::keyboard::replay::parse_replay;
+    use typer_racer::mode::GameMode;
+    use typer_racer::quote_loader::{load_expanded_meditations_from, DEFAULT_QUOTES_PATH};
+    use typer_racer::verify::{verify_replay, ExpectedRace};
+
+    const USAGE: &str = "usage: typer-racer-verify REPLAY.json --quote INDEX --seed SEED [--mode MODE] \
+                         [--wpm CLAIMED] [--quotes FILE]";
+
+    fn fail(message: &str) -> ! {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+
+    fn parse_mode(arg: &str) -> Option<GameMode> {
+        match arg.split_once(':') {
+            None if arg == "race" => Some(GameMode::Race),
+            None if arg == "practice" => Some(GameMode::Practice),
+            Some(("timed", seconds)) => seconds.parse().ok().map(|seconds| GameMode::Timed { seconds }),
+            Some(("words", words)) => words.parse().ok().map(|words| GameMode::WordCount { words }),
+            _ => None,
+        }
+    }
+
+    pub fn main() {
+        let mut replay_path = None;
+        let mut claimed_wpm = None;
+        let mut quote_index = None;
+        let mut seed = None;
+        let mut mode = GameMode::Race;
+        let mut quotes_path = DEFAULT_QUOTES_PATH.to_string();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--wpm" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(wpm) => claimed_wpm = Some(wpm),
+                    None => fail(USAGE),
+                },
+                "--quote" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(index) => quote_index = Some(index),
+                    None => fail(USAGE),
+                },
+                "--seed" => seed = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+                "--mode" => match args.next().as_deref().and_then(parse_mode) {
+                    Some(m) => mode = m,
+                    None => fail(USAGE),
+                },
+                "--quotes" => quotes_path = args.next().unwrap_or_else(|| fail(USAGE)),
+                _ if replay_path.is_none() && !arg.starts_with("--") => replay_path = Some(arg),
+                _ => fail(USAGE),
+            }
+        }
+        let replay_path = replay_path.unwrap_or_else(|| fail(USAGE));
+        let (Some(quote_index), Some(seed)) = (quote_index, seed) else {
+            fail(USAGE);
+        };
+        let expected = ExpectedRace { quote_index, seed, mode };
+        let json = std::fs::read_to_string(&replay_path)
+            .unwrap_or_else(|e| fail(&format!("could not read {}: {}", replay_path, e)));
+        let replay = parse_replay(&json).unwrap_or_else(|| fail(&format!("{} is not a replay", replay_path)));
+        let quotes = load_expanded_meditations_from(&quotes_path);
+        if quotes.is_empty() {
+            fail(&format!("no passages found in {}", quotes_path));
+        }
+
+        let verdict = verify_replay(&quotes, &replay, &expected, claimed_wpm);
+        println!(
+            "{}: {:.1} WPM, {:.1}% accuracy, {} keys in {:.1}s",
+            replay_path, verdict.wpm, verdict.accuracy, verdict.keystrokes, verdict.elapsed
+        );
+        if verdict.is_plausible() {
+            println!("OK");
+            return;
+        }
+        for suspicion in &verdict.suspicions {
+            println!("FLAGGED: {}", suspicion.describe());
+        }
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    native::main();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,179 @@
+// --- Touch-typing lessons, one keyboard region at a time ---
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::MeditationQuote;
+use crate::keyboard::layout::{KeyboardLayout, BOTTOM_ROW, HOME_ROW, TOP_ROW};
+use crate::rng::SeededRng;
+
+const LESSON_WORDS: usize = 20;
+/// Below this many real words the lesson falls back to made-up key groups
+const MIN_REAL_WORDS: usize = 5;
+const LESSON_PUNCTUATION: [char; 6] = ['.', ',', ';', '\'', '?', '!'];
+
+/// Stages of the lesson track, in the order they unlock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LessonStage {
+    HomeRow,
+    TopRow,
+    BottomRow,
+    Numbers,
+    Punctuation,
+    Capitals,
+}
+
+impl LessonStage {
+    pub const ALL: [LessonStage; 6] = [
+        LessonStage::HomeRow,
+        LessonStage::TopRow,
+        LessonStage::BottomRow,
+        LessonStage::Numbers,
+        LessonStage::Punctuation,
+        LessonStage::Capitals,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LessonStage::HomeRow => "Home Row",
+            LessonStage::TopRow => "Top Row",
+            LessonStage::BottomRow => "Bottom Row",
+            LessonStage::Numbers => "Numbers",
+            LessonStage::Punctuation => "Punctuation",
+            LessonStage::Capitals => "Capitals",
+        }
+    }
+
+    /// WPM needed to pass the stage
+    pub fn target_wpm(&self) -> f32 {
+        match self {
+            LessonStage::HomeRow => 15.0,
+            LessonStage::TopRow => 18.0,
+            LessonStage::BottomRow | LessonStage::Numbers => 20.0,
+            LessonStage::Punctuation => 22.0,
+            LessonStage::Capitals => 25.0,
+        }
+    }
+
+    /// Accuracy percentage needed to pass the stage
+    pub fn target_accuracy(&self) -> f32 {
+        match self {
+            LessonStage::HomeRow | LessonStage::TopRow => 90.0,
+            LessonStage::BottomRow | LessonStage::Numbers => 92.0,
+            LessonStage::Punctuation => 94.0,
+            LessonStage::Capitals => 95.0,
+        }
+    }
+
+    pub fn previous(&self) -> Option<LessonStage> {
+        let index = Self::ALL.iter().position(|s| s == self)?;
+        index.checked_sub(1).map(|i| Self::ALL[i])
+    }
+
+    pub fn passes(&self, wpm: f32, accuracy: f32) -> bool {
+        wpm >= self.target_wpm() && accuracy >= self.target_accuracy()
+    }
+
+    /// Letters practised by this stage and every stage before it
+    pub fn letters(&self, layout: KeyboardLayout) -> BTreeSet<char> {
+        let rows: &[usize] = match self {
+            LessonStage::HomeRow => &[HOME_ROW],
+            LessonStage::TopRow => &[HOME_ROW, TOP_ROW],
+            _ => &[HOME_ROW, TOP_ROW, BOTTOM_ROW],
+        };
+        rows.iter().flat_map(|&row| layout.row_letters(row)).collect()
+    }
+
+    /// Lesson text: real words from the quotes that only use this stage's
+    /// letters, with numbers, punctuation and capitals mixed in once those
+    /// stages are reached. The same seed gives the same lesson.
+    pub fn lesson_text(&self, quotes: &[MeditationQuote], layout: KeyboardLayout, seed: &str) -> String {
+        let mut rng = SeededRng::from_seed(seed);
+        let words = words_from_letters(quotes, &self.letters(layout), &mut rng, LESSON_WORDS);
+        let mut lesson = Vec::with_capacity(words.len());
+        for mut word in words {
+            if *self >= LessonStage::Numbers && rng.index(4) == 0 {
+                let len = 2 + rng.index(3);
+                word = (0..len).map(|_| char::from(b'0' + rng.index(10) as u8)).collect();
+            }
+            if *self >= LessonStage::Punctuation && rng.index(3) == 0 {
+                word.push(LESSON_PUNCTUATION[rng.index(LESSON_PUNCTUATION.len())]);
+            }
+            if *self >= LessonStage::Capitals && rng.index(3) == 0 {
+                let mut chars = word.chars();
+                if let Some(first) = chars.next() {
+                    word = first.to_uppercase().chain(chars).collect();
+                }
+            }
+            lesson.push(word);
+        }
+        lesson.join(" ")
+    }
+}
+
+/// `count` lowercase words from the quotes spelled only with `letters`.
+/// When the quotes have too few, made-up groups of the letters are used.
+pub fn words_from_letters(
+    quotes: &[MeditationQuote],
+    letters: &BTreeSet<char>,
+    rng: &mut SeededRng,
+    count: usize,
+) -> Vec<String> {
+    let words: Vec<String> = quotes
+        .iter()
+        .flat_map(|q| q.expanded_meditation.split_whitespace())
+        .map(|w| w.chars().filter(|c| c.is_alphabetic()).flat_map(char::to_lowercase).collect::<String>())
+        .filter(|w| !w.is_empty() && w.chars().all(|c| letters.contains(&c)))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if words.len() >= MIN_REAL_WORDS {
+        return (0..count).map(|_| words[rng.index(words.len())].clone()).collect();
+    }
+    let letters: Vec<char> = letters.iter().copied().collect();
+    if letters.is_empty() {
+        return Vec::new();
+    }
+    (0..count)
+        .map(|_| {
+            let len = 2 + rng.index(4);
+            (0..len).map(|_| letters[rng.index(letters.len())]).collect()
+        })
+        .collect()
+}
+
+/// Best result on one stage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LessonResult {
+    pub best_wpm: f32,
+    pub best_accuracy: f32,
+    pub passed: bool,
+}
+
+/// A player's progress through the lesson track
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LessonProgress {
+    pub results: BTreeMap<LessonStage, LessonResult>,
+}
+
+impl LessonProgress {
+    pub fn passed(&self, stage: LessonStage) -> bool {
+        self.results.get(&stage).is_some_and(|r| r.passed)
+    }
+
+    /// The first stage is always open; each later one opens when the
+    /// stage before it is passed
+    pub fn is_unlocked(&self, stage: LessonStage) -> bool {
+        stage.previous().is_none_or(|previous| self.passed(previous))
+    }
+
+    /// Record an attempt. Returns true if it passed the stage for the first time.
+    pub fn record(&mut self, stage: LessonStage, wpm: f32, accuracy: f32) -> bool {
+        let result = self.results.entry(stage).or_default();
+        result.best_wpm = result.best_wpm.max(wpm);
+        result.best_accuracy = result.best_accuracy.max(accuracy);
+        let newly_passed = !result.passed && stage.passes(wpm, accuracy);
+        result.passed |= newly_passed;
+        newly_passed
+    }
+}
@@ -0,0 +1,129 @@
+// --- Adaptive drills built from a player's weakest keys ---
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::MeditationQuote;
+use crate::keyboard::layout::unshifted;
+use crate::rng::SeededRng;
+use crate::stats::KeyStats;
+
+pub const MIN_LEVEL: u8 = 1;
+pub const MAX_LEVEL: u8 = 5;
+const WEAK_KEYS: usize = 4;
+const WEAK_BIGRAMS: usize = 3;
+
+/// What a drill targets. Stored in the race config so the same drill can be
+/// rebuilt from its seed for replays and ghosts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DrillSpec {
+    pub keys: Vec<char>,
+    pub bigrams: Vec<String>,
+    pub level: u8,
+}
+
+impl DrillSpec {
+    pub fn from_stats(stats: &KeyStats, level: u8) -> Self {
+        Self {
+            // Drill words have no spaces to practise
+            keys: stats
+                .weakest_keys(WEAK_KEYS + 1)
+                .into_iter()
+                .filter(|&k| k != ' ')
+                .take(WEAK_KEYS)
+                .collect(),
+            bigrams: stats.weakest_bigrams(WEAK_BIGRAMS),
+            level: level.clamp(MIN_LEVEL, MAX_LEVEL),
+        }
+    }
+
+    fn level(&self) -> u8 {
+        self.level.clamp(MIN_LEVEL, MAX_LEVEL)
+    }
+
+    /// Higher levels are longer drills
+    pub fn word_count(&self) -> usize {
+        10 + 5 * self.level() as usize
+    }
+
+    /// Higher levels allow longer words
+    pub fn max_word_len(&self) -> usize {
+        3 + 2 * self.level() as usize
+    }
+
+    /// Early levels drop capitals and punctuation; later ones keep words as
+    /// they appear in the quotes
+    fn shape_word(&self, word: &str) -> String {
+        match self.level() {
+            1 | 2 => word
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .flat_map(char::to_lowercase)
+                .collect(),
+            3 => word.chars().filter(|c| c.is_alphabetic()).collect(),
+            _ => word.to_string(),
+        }
+    }
+
+    /// How strongly a word exercises the weak keys and bigrams, which are
+    /// kept by unshifted key
+    pub fn score(&self, word: &str) -> usize {
+        let word: String = word.chars().map(unshifted).collect();
+        let keys = word.chars().filter(|c| self.keys.contains(c)).count();
+        let bigrams: usize = self.bigrams.iter().map(|b| word.matches(b.as_str()).count()).sum();
+        keys + 2 * bigrams
+    }
+
+    /// Drill text drawn from words in the quotes, weighted towards words
+    /// that contain the weak patterns. The same seed gives the same drill.
+    pub fn generate(&self, quotes: &[MeditationQuote], seed: &str) -> String {
+        let words: Vec<String> = quotes
+            .iter()
+            .flat_map(|q| q.expanded_meditation.split_whitespace())
+            .map(|w| self.shape_word(w))
+            .filter(|w| !w.is_empty() && w.chars().count() <= self.max_word_len())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        if words.is_empty() {
+            return String::new();
+        }
+        let weights: Vec<usize> = words.iter().map(|w| 1 + 4 * self.score(w)).collect();
+        let total: usize = weights.iter().sum();
+        let mut rng = SeededRng::from_seed(seed);
+        let mut drill: Vec<&str> = Vec::with_capacity(self.word_count());
+        while drill.len() < self.word_count() {
+            let mut pick = rng.index(total);
+            let index = weights
+                .iter()
+                .position(|&w| {
+                    if pick < w {
+                        true
+                    } else {
+                        pick -= w;
+                        false
+                    }
+                })
+                .unwrap_or(0);
+            let word = words[index].as_str();
+            // Avoid the same word twice in a row unless nothing else exists
+            if words.len() > 1 && drill.last() == Some(&word) {
+                continue;
+            }
+            drill.push(word);
+        }
+        drill.join(" ")
+    }
+}
+
+/// Level for the next drill: up after a clean run, down after a rough one
+pub fn next_level(level: u8, accuracy: f32) -> u8 {
+    let level = level.clamp(MIN_LEVEL, MAX_LEVEL);
+    if accuracy >= 96.0 {
+        (level + 1).min(MAX_LEVEL)
+    } else if accuracy < 90.0 {
+        level.saturating_sub(1).max(MIN_LEVEL)
+    } else {
+        level
+    }
+}
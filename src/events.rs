@@ -0,0 +1,44 @@
+// --- Game events: what happened in a race, for sections and sounds to react to ---
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// A word was typed through to its trailing space or the end of the passage
+    WordCompleted { word: String, clean: bool },
+    /// A wrong key at `index` of the passage
+    ErrorMade { index: usize, expected: char, typed: char },
+    RaceFinished { wpm: f32, accuracy: f32 },
+    /// The player and the ghost swapped places; `player_ahead` says who leads now
+    GhostOvertook { player_ahead: bool },
+}
+
+pub trait GameEventHandler {
+    fn handle_game_event(&mut self, event: &GameEvent);
+}
+
+/// Events raised this frame, waiting to be delivered
+#[derive(Debug, Clone, Default)]
+pub struct EventBus {
+    pending: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn publish(&mut self, event: GameEvent) {
+        self.pending.push(event);
+    }
+
+    pub fn publish_all(&mut self, events: impl IntoIterator<Item = GameEvent>) {
+        self.pending.extend(events);
+    }
+
+    /// Hand every pending event, oldest first, to each subscriber in turn.
+    /// Returns the delivered events and leaves the bus empty.
+    pub fn deliver(&mut self, subscribers: &mut [&mut dyn GameEventHandler]) -> Vec<GameEvent> {
+        let events = std::mem::take(&mut self.pending);
+        for event in &events {
+            for subscriber in subscribers.iter_mut() {
+                subscriber.handle_game_event(event);
+            }
+        }
+        events
+    }
+}
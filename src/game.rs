@@ -1,6 +1,15 @@
 // --- Game State and Data Models ---
 use serde::{Deserialize, Serialize};
-// use std::time::Instant; // Removed for WASM compatibility
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::drill::DrillSpec;
+use crate::events::GameEvent;
+use crate::keyboard::layout::KeyboardLayout;
+use crate::mode::{GameMode, WordStream};
+use crate::policy::ErrorPolicy;
+use crate::rng::fnv_hash;
+use crate::review::warm_up_text;
+use crate::versus::hand_drill;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeditationQuote {
@@ -8,26 +17,94 @@ pub struct MeditationQuote {
     pub expanded_meditation: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Recorded in place of a character when the player presses backspace
+pub const BACKSPACE: char = '\u{8}';
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ReplayEvent {
+    /// Race clock reading when the key was pressed. Only the gaps between
+    /// events mean anything; the name is kept so saved replays still load.
     pub milliseconds_since_epoch_utc: u64,
     pub quote_index: usize,
     pub character: char,
 }
 
-#[derive(Debug, PartialEq)]
+/// Everything needed to set up the same race again: which passage, the
+/// level seed, the game mode and how mistakes are handled
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RaceConfig {
+    pub quote_index: usize,
+    pub seed: String,
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub policy: ErrorPolicy,
+    /// Set for drills, which build their text from it
+    #[serde(default)]
+    pub drill: Option<DrillSpec>,
+    /// Layout the lesson text was built for
+    #[serde(default)]
+    pub layout: KeyboardLayout,
+    /// Words typed in a warm-up round
+    #[serde(default)]
+    pub warm_up: Vec<String>,
+}
+
+/// A stretch of a race spent paused, in clock milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PauseSpan {
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// A recorded run together with the race it was recorded on
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub config: RaceConfig,
+    pub events: Vec<ReplayEvent>,
+    #[serde(default)]
+    pub pauses: Vec<PauseSpan>,
+}
+
+impl Replay {
+    /// The keys with paused time cut out, so the gaps between them are
+    /// only time spent racing
+    pub fn active_events(&self) -> Vec<ReplayEvent> {
+        self.events
+            .iter()
+            .map(|event| {
+                let at = event.milliseconds_since_epoch_utc;
+                let paused: u64 = self
+                    .pauses
+                    .iter()
+                    .filter(|p| p.start_ms <= at)
+                    .map(|p| p.end_ms.min(at).saturating_sub(p.start_ms))
+                    .sum();
+                ReplayEvent { milliseconds_since_epoch_utc: at - paused.min(at), ..event.clone() }
+            })
+            .collect()
+    }
+
+    /// Keys recorded while the race was paused, which a real run cannot have
+    pub fn keys_while_paused(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|e| {
+                let at = e.milliseconds_since_epoch_utc;
+                self.pauses.iter().any(|p| p.start_ms < at && at < p.end_ms)
+            })
+            .count()
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
 pub enum GameStatus {
     NotStarted,
+    #[default]
     Running,
     Finished,
 }
 
-impl Default for GameStatus {
-    fn default() -> Self {
-        GameStatus::Running
-    }
-}
-
 #[derive(Default)]
 pub struct GameState {
     pub quotes: Vec<MeditationQuote>,
@@ -37,11 +114,33 @@ pub struct GameState {
     pub cursor_pos: usize, // New: cursor position in input_buffer
     pub status: GameStatus,
     pub errors: usize,
-    pub start_time: Option<crate::time::TimeInstant>,
+    /// Clock reading in milliseconds when the race began
+    pub started_at_ms: Option<u64>,
+    /// Pauses so far, and when the current one began
+    pub pauses: Vec<PauseSpan>,
+    pub paused_at_ms: Option<u64>,
     pub elapsed: f32,
     pub replay: Vec<ReplayEvent>,
     pub ghost_mode: bool,
     pub seed: String,
+    pub mode: GameMode,
+    /// The passage being typed, fixed for races and growing for timed runs
+    pub text: String,
+    pub word_stream: Option<WordStream>,
+    pub policy: ErrorPolicy,
+    /// Character keys pressed this race, excluding backspace
+    pub keystrokes: usize,
+    /// Positions in the passage that were mistyped at least once
+    pub mistakes: BTreeSet<usize>,
+    /// Characters typed past the end of a word, by the position of the
+    /// space they were typed at. They wait there until deleted, and a
+    /// space moves on past them.
+    pub extras: BTreeMap<usize, String>,
+    pub drill: Option<DrillSpec>,
+    pub layout: KeyboardLayout,
+    pub warm_up: Vec<String>,
+    /// Events raised by typing since the last `take_events`
+    pub events: Vec<GameEvent>,
 }
 
 impl GameState {
@@ -52,3 +151,334 @@ impl GameState {
         }
     }
 }
+
+impl GameState {
+    /// The passage being typed in the current race, or "" when no quotes are loaded
+    pub fn target_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn race_config(&self) -> RaceConfig {
+        RaceConfig {
+            quote_index: self.current_quote,
+            seed: self.seed.clone(),
+            mode: self.mode,
+            policy: self.policy,
+            drill: self.drill.clone(),
+            layout: self.layout,
+            warm_up: self.warm_up.clone(),
+        }
+    }
+
+    /// Begin a race with the clock reading `now_ms`. Later times passed to
+    /// `tick` are measured from it, so any clock can drive the race.
+    /// Returns false, leaving the game as it was, when `config` names a quote
+    /// that isn't loaded or otherwise produces no text to type.
+    pub fn start_race_at(&mut self, config: &RaceConfig, now_ms: u64) -> bool {
+        let quote_index = config.quote_index;
+        let seed = config.seed.as_str();
+        let mut word_stream = None;
+        let text = match config.mode {
+            GameMode::Race | GameMode::Practice => match self.quotes.get(quote_index) {
+                Some(quote) => quote.expanded_meditation.clone(),
+                None => return false,
+            },
+            GameMode::Timed { .. } => {
+                let mut stream = WordStream::new(seed);
+                let text = stream.next_words(&self.quotes, WordStream::TIMED_CHUNK);
+                word_stream = Some(stream);
+                text
+            }
+            GameMode::WordCount { words } => WordStream::new(seed).next_words(&self.quotes, words),
+            GameMode::Drill => config
+                .drill
+                .as_ref()
+                .map(|spec| spec.generate(&self.quotes, seed))
+                .unwrap_or_default(),
+            GameMode::Lesson { stage } => stage.lesson_text(&self.quotes, config.layout, seed),
+            GameMode::WarmUp => warm_up_text(&config.warm_up),
+            GameMode::HalfKeyboard { left } => hand_drill(&self.quotes, config.layout, left, seed),
+        };
+        if text.is_empty() {
+            return false;
+        }
+        self.text = text;
+        self.word_stream = word_stream;
+        self.mode = config.mode;
+        self.policy = config.policy;
+        self.drill = config.drill.clone();
+        self.layout = config.layout;
+        self.warm_up = config.warm_up.clone();
+        self.keystrokes = 0;
+        self.mistakes.clear();
+        self.extras.clear();
+        self.current_quote = quote_index;
+        self.current_char = 0;
+        self.input_buffer.clear();
+        self.cursor_pos = 0;
+        self.errors = 0;
+        self.elapsed = 0.0;
+        self.replay.clear();
+        self.seed = seed.to_string();
+        self.started_at_ms = Some(now_ms);
+        self.pauses.clear();
+        self.paused_at_ms = None;
+        self.events.clear();
+        self.status = GameStatus::Running;
+        true
+    }
+
+    /// Feed one typed character into the race, enforcing the error policy.
+    /// Returns true if it matched the next character of the passage. Every
+    /// keystroke is recorded in the replay.
+    pub fn type_char(&mut self, c: char, now_ms: u64) -> bool {
+        if c == BACKSPACE {
+            return self.backspace(now_ms);
+        }
+        if self.status != GameStatus::Running || self.is_paused() {
+            return false;
+        }
+        self.record(c, now_ms);
+        self.keystrokes += 1;
+        let Some(expected) = self.target_text().chars().nth(self.current_char) else {
+            return false;
+        };
+        let correct = chars_match(expected, c);
+        if !correct {
+            self.errors += 1;
+            self.mistakes.insert(self.current_char);
+            self.events.push(GameEvent::ErrorMade { index: self.current_char, expected, typed: c });
+        }
+        // Typing on past the end of a word piles up extras in front of its space
+        if expected == ' ' && !correct && self.policy.advances_on_error() {
+            self.extras.entry(self.current_char).or_default().push(c);
+            return false;
+        }
+        let leaves_bad_word = self.policy == ErrorPolicy::WordStrict
+            && expected == ' '
+            && !self.current_word_is_correct();
+        if correct && leaves_bad_word {
+            // Space is right, but the word before it is not yet
+            self.errors += 1;
+            self.mistakes.insert(self.current_char);
+            self.events.push(GameEvent::ErrorMade { index: self.current_char, expected, typed: c });
+            return false;
+        }
+        if correct || self.policy.advances_on_error() {
+            self.input_buffer.push(if correct { expected } else { c });
+            self.current_char += 1;
+            self.cursor_pos = self.input_buffer.len();
+            self.extend_word_stream();
+            self.check_finished();
+            if expected == ' ' || self.status == GameStatus::Finished {
+                let end = self.current_char - usize::from(expected == ' ');
+                self.events.push(self.word_completed(end));
+            }
+        }
+        correct
+    }
+
+    /// The event for the passage word that ends just before `end`
+    fn word_completed(&self, end: usize) -> GameEvent {
+        let before: Vec<char> = self.target_text().chars().take(end).collect();
+        let start = before.iter().rposition(|&c| c == ' ').map(|i| i + 1).unwrap_or(0);
+        GameEvent::WordCompleted {
+            word: before[start..].iter().collect(),
+            clean: self.mistakes.range(start..=end).next().is_none(),
+        }
+    }
+
+    /// Hand over the events raised since the last call
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Remove the last typed character if the error policy allows it
+    pub fn backspace(&mut self, now_ms: u64) -> bool {
+        if self.status != GameStatus::Running || self.is_paused() || !self.policy.allows_backspace() {
+            return false;
+        }
+        self.record(BACKSPACE, now_ms);
+        // Extras past the end of the word go before the word itself
+        if let Some(extra) = self.extras.get_mut(&self.current_char) {
+            extra.pop();
+            if extra.is_empty() {
+                self.extras.remove(&self.current_char);
+            }
+            return true;
+        }
+        if self.input_buffer.pop().is_none() {
+            return false;
+        }
+        self.current_char -= 1;
+        self.cursor_pos = self.input_buffer.len();
+        true
+    }
+
+    fn record(&mut self, c: char, now_ms: u64) {
+        self.replay.push(ReplayEvent {
+            milliseconds_since_epoch_utc: now_ms,
+            quote_index: self.current_quote,
+            character: c,
+        });
+    }
+
+    /// Whether every character typed so far in the current word is right,
+    /// with nothing extra typed after it
+    fn current_word_is_correct(&self) -> bool {
+        if self.extras.contains_key(&self.current_char) {
+            return false;
+        }
+        let word_start = self.input_buffer.rfind(' ').map(|i| i + 1).unwrap_or(0);
+        let start_char = self.input_buffer[..word_start].chars().count();
+        self.input_buffer[word_start..]
+            .chars()
+            .zip(self.target_text().chars().skip(start_char))
+            .all(|(typed, expected)| typed == expected)
+    }
+
+    fn check_finished(&mut self) {
+        if self.current_char < self.target_text().chars().count() {
+            return;
+        }
+        // Word-strict runs must fix the last word before the race ends
+        if self.policy == ErrorPolicy::WordStrict && !self.current_word_is_correct() {
+            return;
+        }
+        self.status = GameStatus::Finished;
+    }
+
+    /// Number of typed characters that match the passage
+    pub fn correct_chars(&self) -> usize {
+        self.input_buffer
+            .chars()
+            .zip(self.target_text().chars())
+            .filter(|(typed, expected)| typed == expected)
+            .count()
+    }
+
+    /// Keep a timed run supplied with words so the player never reaches the end
+    fn extend_word_stream(&mut self) {
+        let Some(stream) = self.word_stream.as_mut() else {
+            return;
+        };
+        let remaining = self.text.chars().count().saturating_sub(self.current_char);
+        if remaining < 80 {
+            let more = stream.next_words(&self.quotes, WordStream::TIMED_CHUNK / 2);
+            if !more.is_empty() {
+                self.text.push(' ');
+                self.text.push_str(&more);
+            }
+        }
+    }
+
+    /// Seconds spent racing since the race began, by the clock reading
+    /// `now_ms`. Paused time does not count.
+    pub fn elapsed_at(&self, now_ms: u64) -> f32 {
+        let Some(start) = self.started_at_ms else {
+            return 0.0;
+        };
+        let paused: u64 = self.pauses.iter().map(|p| p.end_ms.saturating_sub(p.start_ms)).sum::<u64>()
+            + self.paused_at_ms.map(|at| now_ms.saturating_sub(at)).unwrap_or(0);
+        now_ms.saturating_sub(start).saturating_sub(paused) as f32 / 1000.0
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at_ms.is_some()
+    }
+
+    /// Stop the race clock; typing is ignored until `resume`
+    pub fn pause(&mut self, now_ms: u64) {
+        if self.status == GameStatus::Running && !self.is_paused() {
+            self.paused_at_ms = Some(now_ms);
+        }
+    }
+
+    pub fn resume(&mut self, now_ms: u64) {
+        if let Some(start_ms) = self.paused_at_ms.take() {
+            self.pauses.push(PauseSpan { start_ms, end_ms: now_ms.max(start_ms) });
+        }
+    }
+
+    /// This run so far as a replay
+    pub fn recorded_replay(&self) -> Replay {
+        Replay {
+            config: self.race_config(),
+            events: self.replay.clone(),
+            pauses: self.pauses.clone(),
+        }
+    }
+
+    /// Advance the race clock to `now_ms`
+    pub fn tick(&mut self, now_ms: u64) {
+        self.set_elapsed(self.elapsed_at(now_ms));
+    }
+
+    /// Advance the race clock, ending timed runs when their time is up
+    pub fn set_elapsed(&mut self, elapsed: f32) {
+        if self.status != GameStatus::Running {
+            return;
+        }
+        match self.mode.time_limit() {
+            Some(limit) if elapsed >= limit => {
+                self.elapsed = limit;
+                self.status = GameStatus::Finished;
+            }
+            _ => self.elapsed = elapsed,
+        }
+    }
+
+    /// How far along the race is, 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if let Some(limit) = self.mode.time_limit() {
+            return (self.elapsed / limit).clamp(0.0, 1.0);
+        }
+        let total = self.target_text().chars().count();
+        if total > 0 {
+            self.current_char as f32 / total as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Net words per minute so far (5 characters per word)
+    pub fn wpm(&self) -> f32 {
+        let minutes = self.elapsed / 60.0;
+        if minutes > 0.0 {
+            (self.correct_chars() as f32 / 5.0) / minutes
+        } else {
+            0.0
+        }
+    }
+
+    /// Percentage of keystrokes that were correct
+    pub fn accuracy(&self) -> f32 {
+        if self.keystrokes > 0 {
+            self.keystrokes.saturating_sub(self.errors) as f32 / self.keystrokes as f32 * 100.0
+        } else {
+            100.0
+        }
+    }
+}
+
+/// The passage a seed picks out of `quote_count`, so every player given
+/// the same seed races the same text
+pub fn quote_for_seed(seed: &str, quote_count: usize) -> usize {
+    if quote_count == 0 {
+        0
+    } else {
+        (fnv_hash(seed) % quote_count as u64) as usize
+    }
+}
+
+/// Typographic punctuation in the meditations can be typed with its plain
+/// keyboard equivalent
+pub fn chars_match(expected: char, typed: char) -> bool {
+    let plain = |c: char| match c {
+        '\u{2014}' | '\u{2013}' => '-',
+        '\u{2018}' | '\u{2019}' => '\'',
+        '\u{201C}' | '\u{201D}' => '"',
+        other => other,
+    };
+    expected == typed || plain(expected) == typed
+}
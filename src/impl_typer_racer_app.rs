@@ -1,26 +1,929 @@
 use crate::TyperRacerApp;
-use crate::keyboard::event::KeyboardEventHandler;
+use crate::{GameState, GameStatus, RaceConfig, Replay};
+use crate::game::quote_for_seed;
+use crate::audio::Sound;
+use crate::events::GameEvent;
+use crate::keyboard::display::current_word_range;
+use crate::keyboard::dispatch::{dispatch, TypingLayer};
+use crate::keyboard::event::{keyboard_event, KeyboardEvent, KeyboardEventHandler};
+use crate::keyboard::input::TypingBuffer;
+use crate::keyboard::onscreen::render_keyboard;
+use crate::curriculum::LessonStage;
+use crate::drill::DrillSpec;
+use crate::keyboard::layout::KeyboardLayout;
+use crate::keyboard::replay::{ghost_name, load_replay, load_replay_named, save_replay, save_replay_named};
+use crate::keyboard::shortcuts::{load_keybindings, save_keybindings, Command, Keybindings, ShortcutLayer};
+use crate::leaderboard::{load_leaderboard, save_leaderboard, LeaderboardEntry, DEFAULT_PACK};
+use crate::profile::{load_profiles, save_profiles};
+use crate::quote_loader::load_expanded_meditations;
+use crate::mode::GameMode;
+use crate::net::{ClientMessage, SessionEvent, MAX_COUNTDOWN_MS};
+use crate::review::WARM_UP_WORDS;
+use crate::settings::{load_settings, save_settings, GhostChoice};
+use crate::storage::default_storage;
+use crate::theme::{available_themes, find_theme};
+use crate::sections::FooterAction;
+use crate::ui::sections_leaderboard::LeaderboardAction;
+use crate::ui::sections_left::PlayerViewState;
+use crate::ui::sections_results::{ResultsAction, ResultsState};
+use crate::ui::sections_start::StartAction;
+use crate::ui::sections_stats::StatsAction;
+use crate::ui::sections_settings::SettingsAction;
+use crate::ui::sections_shortcuts::{PaletteAction, ShortcutsAction};
+use crate::versus::{split_keyboard_configs, HeadToHead, PlayerResult, VersusMode, VersusState};
+use chrono::Utc;
 use eframe::egui;
 
 impl eframe::App for TyperRacerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if std::mem::take(&mut self.settings_changed) {
+            find_theme(&self.themes, &self.settings.theme)
+                .adapted(self.settings.color_vision, self.settings.cues)
+                .install(ctx);
+            self.settings.apply(ctx);
+        }
+        self.update_online(ctx);
+        self.update_countdown(ctx);
+        let was_running = self.game.status == GameStatus::Running;
         // Update the timer every frame
         self.update_timer(ctx);
 
-        use crate::keyboard::simulator::KeyboardSimulator;
-        // use std::time::{SystemTime, UNIX_EPOCH}; // Removed for WASM compatibility
-        use crate::time::TimeInstant;
-        // ... (other code remains the same)
+        if self.game.status == GameStatus::Running {
+            self.update_pause(ctx);
+        }
+        self.process_input(ctx);
+        if self.game.status == GameStatus::Running && !self.game.is_paused() {
+            // In a split-keyboard race whoever finishes first ends it
+            if self.opponent.status == GameStatus::Finished && self.is_split_keyboard() {
+                self.game.status = GameStatus::Finished;
+            }
 
-        // Process input events
+            // --- Keyboard Replay Simulation ---
+            let now_ms = self.clock.now_ms();
+            self.online.report_progress(&self.game, now_ms);
+            while let Some(event) = self.keyboard_simulator.tick(self.clock.as_ref()) {
+                let _ = self.player2_view.handle_keyboard_event(&event);
+                if let KeyboardEvent::KeyPress { key, .. } = &event {
+                    if key == "BACKSPACE" {
+                        self.opponent.backspace(now_ms);
+                    } else if let Some(c) = key.chars().next() {
+                        self.opponent.type_char(c, now_ms);
+                    }
+                }
+            }
+        }
+        self.collect_game_events();
+        if was_running && self.game.status == GameStatus::Finished {
+            self.finish_race();
+            // A finished warm-up goes straight into the race it was before
+            if let Some(config) = self.pending_race.take() {
+                self.start_race_on(&config);
+            }
+        }
+        self.events.deliver(&mut [
+            &mut self.top_section,
+            &mut self.player1_view,
+            &mut self.announcer,
+            &mut self.audio,
+        ]);
+        self.announcer.show(ctx);
+        self.audio.flush(&self.settings);
 
-        // --- Keyboard Replay Simulation ---
-        let now_ms = (TimeInstant::now().elapsed().as_secs_f32() * 1000.0) as u64;
-        if let Some(event) = self.keyboard_simulator.tick(now_ms) {
-            // For now, send replayed event to player1_view
-            let _ = self.player1_view.handle_keyboard_event(&event);
+        if self.countdown.is_some() {
+            self.render_countdown(ctx);
+            return;
+        }
+        match self.game.status {
+            GameStatus::NotStarted => self.render_start_screen(ctx),
+            GameStatus::Running => {
+                self.update_ui_state();
+                self.render_race(ctx);
+                if self.game.is_paused() && !self.overlay_open() {
+                    self.render_pause_overlay(ctx);
+                }
+            }
+            GameStatus::Finished => self.render_results(ctx),
+        }
+        if self.show_palette {
+            self.render_palette(ctx);
+        }
+        if self.show_settings {
+            self.render_settings(ctx);
+        }
+        if self.show_leaderboard {
+            self.render_leaderboard(ctx);
+        }
+        if self.show_stats {
+            self.render_stats(ctx);
+        }
+    }
+}
+
+impl TyperRacerApp {
+    /// Handle server messages, counting down to an online race when asked
+    fn update_online(&mut self, ctx: &egui::Context) {
+        for event in self.online.poll() {
+            match event {
+                SessionEvent::Countdown { config, countdown_ms } => {
+                    let countdown_ms = countdown_ms.min(MAX_COUNTDOWN_MS);
+                    self.countdown = Some((config, self.clock.now_ms().saturating_add(countdown_ms)));
+                }
+                SessionEvent::RaceOver => self.results.standings = self.online.standings.clone(),
+            }
+        }
+        if self.online.is_connected() {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Start the race that was counting down once its time comes
+    fn update_countdown(&mut self, ctx: &egui::Context) {
+        let Some((config, start_at)) = self.countdown.clone() else {
+            return;
+        };
+        let now_ms = self.clock.now_ms();
+        if now_ms < start_at {
+            let seconds_left = (start_at - now_ms).div_ceil(1000);
+            if self.countdown_beeped != Some(seconds_left) {
+                self.countdown_beeped = Some(seconds_left);
+                self.audio.queue(Sound::CountdownBeep);
+            }
+            ctx.request_repaint();
+            return;
+        }
+        self.countdown = None;
+        self.countdown_beeped = None;
+        self.keyboard_simulator.stop();
+        self.end_versus();
+        let started = self.start_race_on(&RaceConfig { layout: self.layout(), ..config });
+        // A server with a different quote pack can name a passage this build lacks
+        if !started && self.online.racing {
+            self.online.last_error = Some("The server picked a passage this game doesn't have".to_string());
+            self.online.disconnect();
+        }
+    }
+
+    /// Start `config` after the countdown set in settings, or now if it is 0
+    fn start_after_countdown(&mut self, config: RaceConfig) {
+        if self.settings.countdown_secs == 0 {
+            self.start_race_on(&config);
+            return;
+        }
+        let start_at = self.clock.now_ms() + u64::from(self.settings.countdown_secs) * 1000;
+        self.countdown = Some((config, start_at));
+    }
+
+    fn render_countdown(&self, ctx: &egui::Context) {
+        let remaining_ms = self
+            .countdown
+            .as_ref()
+            .map(|(_, start_at)| start_at.saturating_sub(self.clock.now_ms()))
+            .unwrap_or(0);
+        let heading = if self.online.is_connected() { "Online race starting in" } else { "Race starting in" };
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(100.0);
+                ui.heading(heading);
+                ui.heading(format!("{}", remaining_ms.div_ceil(1000).max(1)));
+            });
+        });
+    }
+
+    /// Pause when the window loses focus. Online races keep going for
+    /// everyone else, so they cannot pause.
+    fn update_pause(&mut self, ctx: &egui::Context) {
+        if !self.online.racing && !ctx.input(|i| i.focused) {
+            self.pause_race();
+        }
+    }
+
+    /// Do what a shortcut or the command palette asked for. Commands that
+    /// would leave or stop an online race are ignored during one.
+    pub fn run_command(&mut self, command: Command) {
+        let online = self.online.racing;
+        match command {
+            Command::Restart if !online => {
+                let config = self.game.race_config();
+                let restart = self.game.status != GameStatus::NotStarted;
+                self.abandon_race();
+                if restart {
+                    self.start_race_on(&config);
+                } else {
+                    self.start_race();
+                }
+            }
+            Command::NextQuote if !online => {
+                self.abandon_race();
+                self.start_race();
+            }
+            Command::ToggleGhost => {
+                self.settings.show_ghost = !self.settings.show_ghost;
+                self.save_current_settings();
+            }
+            Command::ToggleKeyboard => {
+                self.settings.show_keyboard = !self.settings.show_keyboard;
+                self.save_current_settings();
+            }
+            Command::Pause if !online => {
+                if self.game.is_paused() {
+                    self.resume_race();
+                } else {
+                    self.pause_race();
+                }
+            }
+            Command::OpenSettings => {
+                // Pick up themes saved since the last time
+                self.themes = available_themes(self.storage.as_ref());
+                self.pause_for_overlay();
+                self.show_settings = true;
+            }
+            Command::SaveGhost => {
+                self.save_ghost();
+            }
+            Command::OpenPalette => {
+                self.pause_for_overlay();
+                self.show_palette = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Drop the ghost, versus session and any pending race before starting over
+    fn abandon_race(&mut self) {
+        self.keyboard_simulator.stop();
+        self.end_versus();
+        self.pending_race = None;
+        self.countdown = None;
+    }
+
+    /// Keep the finished run under its own name so later races don't
+    /// overwrite it
+    pub fn save_ghost(&mut self) -> bool {
+        if self.game.status != GameStatus::Finished || self.game.replay.is_empty() {
+            return false;
+        }
+        let name = ghost_name(&self.game.seed);
+        if !save_replay_named(self.storage.as_mut(), &name, &self.game.recorded_replay()) {
+            return false;
+        }
+        self.results.saved_ghost = Some(name);
+        true
+    }
+
+    fn overlay_open(&self) -> bool {
+        self.show_palette || self.show_settings
+    }
+
+    /// Overlays stop the race clock; `resume_after_overlay` restarts it
+    fn pause_for_overlay(&mut self) {
+        let running = self.game.status == GameStatus::Running && !self.game.is_paused();
+        if running && !self.online.racing {
+            self.pause_race();
+            self.resume_on_close = true;
+        }
+    }
+
+    fn resume_after_overlay(&mut self) {
+        if !self.overlay_open() && std::mem::take(&mut self.resume_on_close) {
+            self.resume_race();
+        }
+    }
+
+    /// Freeze the player clock, the opponent and the ghost playback together
+    pub fn pause_race(&mut self) {
+        let now_ms = self.clock.now_ms();
+        self.game.pause(now_ms);
+        self.opponent.pause(now_ms);
+        self.keyboard_simulator.pause(self.clock.as_ref());
+    }
+
+    pub fn resume_race(&mut self) {
+        let now_ms = self.clock.now_ms();
+        self.game.resume(now_ms);
+        self.opponent.resume(now_ms);
+        self.keyboard_simulator.resume(self.clock.as_ref());
+    }
+
+    fn render_pause_overlay(&mut self, ctx: &egui::Context) {
+        let mut resume = false;
+        egui::Window::new("Paused")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("The clock is stopped.");
+                let label = match self.keybindings.chord(Command::Pause) {
+                    Some(chord) => format!("Resume ({})", chord),
+                    None => "Resume".to_string(),
+                };
+                resume = ui.button(label).clicked();
+            });
+        if resume {
+            self.resume_race();
+        }
+    }
+
+    /// Route this frame's input through the chain: shortcuts, then any open
+    /// overlay, then the race, then the on-screen keyboard. The first link
+    /// that handles an event keeps it from the rest.
+    fn process_input(&mut self, ctx: &egui::Context) {
+        // Unmodified chords would fire while typing in a text field
+        let text_focus = ctx.wants_keyboard_input() || self.overlay_open();
+        let events: Vec<KeyboardEvent> = ctx.input(|i| i.events.iter().filter_map(keyboard_event).collect());
+        // An Alt chord also arrives as the letter it would type, which must
+        // not reach the passage
+        let mut chord_text = false;
+        for event in events {
+            if std::mem::take(&mut chord_text) && matches!(event, KeyboardEvent::Text { .. }) {
+                continue;
+            }
+            if let KeyboardEvent::KeyPress { key, .. } = &event {
+                self.last_pressed_key = Some(key.clone());
+            }
+            let racing = self.game.status == GameStatus::Running;
+            let split = self.is_split_keyboard();
+            let keystrokes = self.game.keystrokes;
+            // A chord being captured for rebinding must not run its old command
+            let capturing = self.show_settings && self.shortcut_editor.capturing.is_some();
+            let mut shortcuts = ShortcutLayer::new(&self.keybindings, racing, text_focus);
+            let mut typing = TypingLayer {
+                game: &mut self.game,
+                opponent: &mut self.opponent,
+                split,
+                now_ms: self.clock.now_ms(),
+            };
+            let mut chain: Vec<&mut dyn KeyboardEventHandler> = Vec::new();
+            if !capturing {
+                chain.push(&mut shortcuts);
+            }
+            if self.show_palette {
+                chain.push(&mut self.palette);
+            }
+            if self.show_settings {
+                chain.push(&mut self.shortcut_editor);
+            }
+            chain.push(&mut typing);
+            chain.push(&mut self.keyboard_display);
+            dispatch(&event, &mut chain);
+            if self.game.keystrokes > keystrokes {
+                self.audio.queue(Sound::Click);
+            }
+            chord_text = !shortcuts.fired.is_empty()
+                && matches!(&event, KeyboardEvent::KeyPress { modifiers, .. } if modifiers.alt && !modifiers.ctrl);
+            for command in shortcuts.fired {
+                self.run_command(command);
+            }
+        }
+        self.typing.set_buffer(&self.game.input_buffer);
+    }
+
+    /// Gather what happened in the race this frame for `events` to deliver
+    fn collect_game_events(&mut self) {
+        self.events.publish_all(self.game.take_events());
+        // The opponent lane's own words and errors are not announced
+        self.opponent.take_events();
+        if self.opponent.status == GameStatus::NotStarted || self.game.status == GameStatus::NotStarted {
+            return;
+        }
+        let (player, opponent) = (self.game.progress(), self.opponent.progress());
+        if player == opponent {
+            return;
+        }
+        let ahead = player > opponent;
+        if self.player_ahead.is_some_and(|was| was != ahead) {
+            self.events.publish(GameEvent::GhostOvertook { player_ahead: ahead });
+        }
+        self.player_ahead = Some(ahead);
+    }
+
+    /// Start a race in the chosen mode, after a warm-up round if the
+    /// player has problem words due
+    pub fn start_race(&mut self) {
+        self.keyboard_simulator.stop();
+        let config = self.new_race_config();
+        let due = self
+            .profiles
+            .active()
+            .map(|p| p.reviews.due(Utc::now(), WARM_UP_WORDS))
+            .unwrap_or_default();
+        if self.start_screen.skip_warm_up || due.is_empty() {
+            self.start_after_countdown(config);
+            return;
+        }
+        self.start_after_countdown(RaceConfig {
+            seed: format!("{}-warm-up", config.seed),
+            mode: GameMode::WarmUp,
+            policy: config.policy,
+            warm_up: due,
+            ..RaceConfig::default()
+        });
+        self.pending_race = Some(config);
+    }
+
+    /// Pick a quote from a fresh seed for the mode chosen on the start screen
+    fn new_race_config(&self) -> RaceConfig {
+        let seed = Utc::now().timestamp_millis().to_string();
+        let quote_index = quote_for_seed(&seed, self.game.quotes.len());
+        let mode = self.start_screen.mode;
+        // Drills target the active player's weakest keys so far
+        let drill = (mode == GameMode::Drill).then(|| {
+            self.profiles
+                .active()
+                .map(|p| DrillSpec::from_stats(&p.key_stats, p.drill_level))
+                .unwrap_or_default()
+        });
+        RaceConfig {
+            quote_index,
+            seed,
+            mode,
+            policy: self.settings.policy,
+            drill,
+            layout: self.layout(),
+            ..RaceConfig::default()
+        }
+    }
+
+    /// Begin a head-to-head between the active player and the second
+    /// player chosen on the start screen
+    pub fn start_versus(&mut self) {
+        let (Some(first), Some(second)) = (
+            self.profiles.active().map(|p| p.name.clone()),
+            self.start_screen.second_player.clone(),
+        ) else {
+            return;
+        };
+        let mode = self.start_screen.versus_mode;
+        self.keyboard_simulator.stop();
+        self.versus = Some(VersusState {
+            mode,
+            players: [first, second],
+            first: None,
+        });
+        match mode {
+            VersusMode::HotSeat => {
+                let config = self.new_race_config();
+                self.start_race_on(&config);
+            }
+            VersusMode::SplitKeyboard => {
+                let seed = Utc::now().timestamp_millis().to_string();
+                let (left, right) = split_keyboard_configs(&seed, self.layout(), self.settings.policy);
+                self.start_race_on(&left);
+                self.opponent.quotes = self.game.quotes.clone();
+                self.opponent.start_race_at(&right, self.clock.now_ms());
+            }
+        }
+    }
+
+    /// Drop any head-to-head, handing the game back to the player who began it
+    fn end_versus(&mut self) {
+        if let Some(versus) = self.versus.take() {
+            self.restore_first_player(&versus);
+        }
+    }
+
+    /// Make the head-to-head's first player active again after a hot-seat
+    /// turn selected the second
+    fn restore_first_player(&mut self, versus: &VersusState) {
+        let first = versus.players[0].as_str();
+        if self.profiles.active().is_some_and(|p| p.name != first) && self.profiles.select(first) {
+            self.apply_profile_settings();
+        }
+    }
+
+    fn is_split_keyboard(&self) -> bool {
+        self.versus.as_ref().is_some_and(|v| v.mode == VersusMode::SplitKeyboard)
+    }
+
+    /// Hand a hot-seat race to the second player, racing the first
+    /// player's run as a live ghost
+    fn next_hot_seat_player(&mut self) {
+        let Some(next) = self.results.next_player.clone() else {
+            return;
+        };
+        let config = self.game.race_config();
+        let replay = self.game.recorded_replay();
+        self.profiles.select(&next);
+        self.apply_profile_settings();
+        self.race_replay(replay, &config);
+    }
+
+    /// Use the active player's own settings, or the machine's if they have none
+    fn apply_profile_settings(&mut self) {
+        self.settings = self
+            .profiles
+            .active()
+            .and_then(|p| p.settings.clone())
+            .unwrap_or_else(|| self.machine_settings.clone());
+        self.settings_changed = true;
+    }
+
+    /// Save the settings in use where they came from: the active player's
+    /// own, or the machine's
+    fn save_current_settings(&mut self) {
+        match self.profiles.active_mut() {
+            Some(profile) if profile.settings.is_some() => {
+                profile.settings = Some(self.settings.clone());
+                save_profiles(self.storage.as_mut(), &self.profiles);
+            }
+            _ => {
+                self.machine_settings = self.settings.clone();
+                save_settings(self.storage.as_mut(), &self.settings);
+            }
+        }
+    }
+
+    /// Name shown on the right lane
+    fn opponent_name(&self) -> String {
+        match &self.versus {
+            Some(versus) if versus.mode == VersusMode::SplitKeyboard => versus.players[1].clone(),
+            Some(versus) => versus.players[0].clone(),
+            None => "Ghost".to_string(),
+        }
+    }
+
+    /// Start a lesson stage on the active player's keyboard layout
+    pub fn start_lesson(&mut self, stage: LessonStage) {
+        self.keyboard_simulator.stop();
+        self.start_race_on(&RaceConfig {
+            seed: Utc::now().timestamp_millis().to_string(),
+            mode: GameMode::Lesson { stage },
+            policy: self.settings.policy,
+            layout: self.layout(),
+            ..RaceConfig::default()
+        });
+    }
+
+    fn layout(&self) -> KeyboardLayout {
+        self.settings.layout
+    }
+
+    /// Race a saved replay as a ghost on the race it was recorded with.
+    /// `fallback` is used for old replays saved without their config.
+    pub fn race_ghost(&mut self, replay_ref: &str, fallback: &RaceConfig) {
+        let replay = load_replay_named(self.storage.as_ref(), replay_ref);
+        self.race_replay(replay, fallback);
+    }
+
+    /// Race the ghost picked in settings on the passage just finished
+    fn race_chosen_ghost(&mut self) {
+        let best = match self.settings.ghost {
+            GhostChoice::LastRun => None,
+            GhostChoice::PersonalBest => self.profiles.active().and_then(|profile| {
+                profile
+                    .history
+                    .iter()
+                    .filter(|r| r.quote_index == self.game.current_quote && r.mode == self.game.mode)
+                    .max_by(|a, b| a.wpm.total_cmp(&b.wpm))
+                    .map(|r| r.replay_ref.clone())
+            }),
+        };
+        match best {
+            Some(replay_ref) => self.race_ghost(&replay_ref, &self.game.race_config()),
+            None => self.race_last_ghost(),
+        }
+    }
+
+    /// Race the most recent run again as a ghost
+    pub fn race_last_ghost(&mut self) {
+        let fallback = self.game.race_config();
+        let replay = load_replay(self.storage.as_ref());
+        self.race_replay(replay, &fallback);
+    }
+
+    fn race_replay(&mut self, replay: Replay, fallback: &RaceConfig) {
+        let config = if replay.config.seed.is_empty() {
+            fallback.clone()
+        } else {
+            replay.config.clone()
+        };
+        self.start_race_on(&config);
+        if !config.mode.allows_ghost() {
+            return;
+        }
+        self.opponent.quotes = self.game.quotes.clone();
+        self.opponent.start_race_at(&config, self.clock.now_ms());
+        self.keyboard_simulator
+            .start_replay(replay.active_events(), self.clock.as_ref());
+    }
+
+    /// Start `config` now. False, with nothing started, when it has no text.
+    fn start_race_on(&mut self, config: &RaceConfig) -> bool {
+        if !self.game.start_race_at(config, self.clock.now_ms()) {
+            return false;
+        }
+        self.typing = TypingBuffer::default();
+        self.footer.wpm_history.clear();
+        self.footer.current_mode = config.mode;
+        self.player1_view = PlayerViewState::default();
+        self.player2_view = PlayerViewState {
+            opponent: true,
+            ..PlayerViewState::default()
+        };
+        self.top_section.player2_wpm = 0.0;
+        self.top_section.ghost_lead = None;
+        self.player_ahead = None;
+        self.announcer.reset();
+        self.keyboard_display = Default::default();
+        self.opponent = GameState {
+            status: GameStatus::NotStarted,
+            ..GameState::default()
+        };
+        true
+    }
+
+    /// Save the replay and add the run to the active profile's history
+    fn finish_race(&mut self) {
+        // Timed runs already stopped the clock exactly at their limit
+        if self.game.mode.time_limit().is_none() {
+            self.game.elapsed = self.game.elapsed_at(self.clock.now_ms());
+        }
+        self.events.publish(GameEvent::RaceFinished {
+            wpm: self.game.wpm(),
+            accuracy: self.game.accuracy(),
+        });
+        self.results = ResultsState {
+            wpm: self.game.wpm(),
+            accuracy: self.game.accuracy(),
+            errors: self.game.errors,
+            elapsed: self.game.elapsed,
+            seed: self.game.seed.clone(),
+            ..ResultsState::default()
+        };
+        let replay = self.game.recorded_replay();
+        save_replay(self.storage.as_mut(), &replay);
+        let profile_name = self.profiles.active().map(|p| p.name.clone());
+        let replay_ref = format!("{}-{}", profile_name.clone().unwrap_or_default(), self.game.seed);
+        if profile_name.is_some() {
+            save_replay_named(self.storage.as_mut(), &replay_ref, &replay);
+            self.results.lesson = self.profiles.record_finished(&self.game, &replay, &replay_ref);
+            save_profiles(self.storage.as_mut(), &self.profiles);
+        }
+        if let (Some(profile_name), true) = (profile_name, self.game.mode.is_ranked()) {
+            self.leaderboard.add(LeaderboardEntry {
+                player: profile_name,
+                date: Utc::now(),
+                pack: DEFAULT_PACK.to_string(),
+                quote_index: self.game.current_quote,
+                mode: self.game.mode,
+                policy: self.game.policy,
+                seed: self.game.seed.clone(),
+                wpm: self.results.wpm,
+                accuracy: self.results.accuracy,
+                replay_ref,
+            });
+            save_leaderboard(self.storage.as_mut(), &self.leaderboard);
+        }
+        self.keyboard_simulator.stop();
+        self.finish_versus();
+        self.online.report_finish(&self.game);
+    }
+
+    /// Record a head-to-head result, or hand over to the next hot-seat player
+    fn finish_versus(&mut self) {
+        let Some(mut versus) = self.versus.take() else {
+            return;
+        };
+        let current = self.profiles.active().map(|p| p.name.clone()).unwrap_or_default();
+        let result = PlayerResult::from_game(&current, &self.game);
+        let players = match (versus.mode, versus.first.take()) {
+            (VersusMode::SplitKeyboard, _) => {
+                self.opponent.elapsed = self.game.elapsed.min(self.opponent.elapsed_at(self.clock.now_ms()));
+                self.opponent.status = GameStatus::Finished;
+                [result, PlayerResult::from_game(&versus.players[1], &self.opponent)]
+            }
+            (VersusMode::HotSeat, Some(first)) => {
+                self.restore_first_player(&versus);
+                [first, result]
+            }
+            (VersusMode::HotSeat, None) => {
+                self.results.next_player = Some(versus.players[1].clone());
+                versus.first = Some(result);
+                self.versus = Some(versus);
+                return;
+            }
+        };
+        self.results.head_to_head = Some(HeadToHead { players });
+    }
+
+    fn render_start_screen(&mut self, ctx: &egui::Context) {
+        let action = egui::CentralPanel::default()
+            .show(ctx, |ui| self.start_screen.render(ui, &mut self.profiles, &self.online))
+            .inner;
+        match action {
+            StartAction::StartRace => self.start_race(),
+            StartAction::StartLesson(stage) => self.start_lesson(stage),
+            StartAction::ShowStats => self.show_stats = true,
+            StartAction::ShowSettings => self.run_command(Command::OpenSettings),
+            StartAction::ConnectOnline => {
+                let name = self.profiles.active().map(|p| p.name.clone()).unwrap_or_default();
+                self.online.connect(&self.start_screen.server_url, &name);
+            }
+            StartAction::DisconnectOnline => self.online.disconnect(),
+            StartAction::SetReady(ready) => {
+                self.online.send(&ClientMessage::Ready { ready });
+            }
+            StartAction::StartVersus => self.start_versus(),
+            StartAction::ProfilesChanged => {
+                self.apply_profile_settings();
+                save_profiles(self.storage.as_mut(), &self.profiles);
+            }
+            StartAction::None => {}
+        }
+    }
+
+    fn render_race(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("top_section").show(ctx, |ui| {
+            if self.game.mode.shows_clock() {
+                self.render_timer(ui);
+            }
+            self.top_section.render(ui);
+        });
+        let footer_action = egui::TopBottomPanel::bottom("footer_section")
+            .show(ctx, |ui| self.footer.render_footer_section(ui))
+            .inner;
+        if footer_action == FooterAction::ShowLeaderboard {
+            self.open_leaderboard();
+        }
+        egui::SidePanel::left("left_section").show(ctx, |ui| {
+            let name = self.profiles.active().map(|p| p.name.clone()).unwrap_or_default();
+            self.player1_view.render(ui, &name);
+        });
+        let opponent_name = self.opponent_name();
+        egui::SidePanel::right("right_section").show(ctx, |ui| {
+            if self.opponent.status != GameStatus::NotStarted || !self.online.racing {
+                self.player2_view.render(ui, &opponent_name);
+            }
+            // Online opponents as extra cars
+            for racer in self.online.racers.values() {
+                ui.add_space(10.0);
+                let car = PlayerViewState {
+                    car_position: racer.progress(),
+                    speed: racer.wpm,
+                    opponent: true,
+                    ..PlayerViewState::default()
+                };
+                let title = match racer.place {
+                    Some(place) => format!("{} (#{})", racer.name, place),
+                    None => racer.name.clone(),
+                };
+                car.render(ui, &title);
+            }
+        });
+        let split = self.is_split_keyboard();
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if split {
+                let name = self.profiles.active().map(|p| p.name.clone()).unwrap_or_default();
+                ui.strong(format!("{} - left hand", name));
+                self.text_input.render(ui);
+                ui.add_space(10.0);
+                ui.strong(format!("{} - right hand", opponent_name));
+                self.player2_input.render(ui);
+            } else {
+                self.text_input.render(ui);
+            }
+            if self.settings.show_keyboard && !split {
+                ui.add_space(10.0);
+                let next_char = self.game.target_text().chars().nth(self.game.current_char);
+                render_keyboard(ui, self.game.layout, next_char, &self.keyboard_display.pressed, &mut |_| {});
+            }
+        });
+    }
+
+    fn open_leaderboard(&mut self) {
+        self.leaderboard_view.current_quote = Some(self.game.current_quote);
+        self.show_leaderboard = true;
+    }
+
+    fn render_leaderboard(&mut self, ctx: &egui::Context) {
+        let action = egui::Window::new("Leaderboard")
+            .collapsible(false)
+            .show(ctx, |ui| self.leaderboard_view.render(ui, &self.leaderboard))
+            .and_then(|r| r.inner)
+            .unwrap_or(LeaderboardAction::None);
+        match action {
+            LeaderboardAction::Close => self.show_leaderboard = false,
+            LeaderboardAction::RaceGhost { replay_ref, config } => {
+                self.show_leaderboard = false;
+                self.race_ghost(&replay_ref, &config);
+            }
+            LeaderboardAction::None => {}
+        }
+    }
+
+    fn render_palette(&mut self, ctx: &egui::Context) {
+        let action = egui::Window::new("Commands")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .show(ctx, |ui| self.palette.render(ui, &self.keybindings))
+            .and_then(|r| r.inner)
+            .unwrap_or(PaletteAction::None);
+        match action {
+            PaletteAction::Close => self.close_palette(),
+            PaletteAction::Run(command) => {
+                self.close_palette();
+                self.run_command(command);
+            }
+            PaletteAction::None => {}
+        }
+    }
+
+    fn close_palette(&mut self) {
+        self.show_palette = false;
+        self.palette.query.clear();
+        self.resume_after_overlay();
+    }
+
+    fn render_settings(&mut self, ctx: &egui::Context) {
+        let theme_names: Vec<String> = self.themes.iter().map(|t| t.name.clone()).collect();
+        let profile = self.profiles.active().map(|p| (p.name.clone(), p.settings.is_some()));
+        // Find out whether sound works before any is played, so settings can say
+        if self.settings.sound {
+            self.audio.open_speaker();
+        }
+        let mut open = true;
+        let (settings_action, action) = egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                let profile = profile.as_ref().map(|(name, own)| (name.as_str(), *own));
+                let settings_action = self.settings_view.render(
+                    ui,
+                    &mut self.settings,
+                    &theme_names,
+                    profile,
+                    self.audio.problem(),
+                );
+                let action = egui::CollapsingHeader::new("Keyboard shortcuts")
+                    .show(ui, |ui| self.shortcut_editor.render(ui, &self.keybindings))
+                    .body_returned
+                    .unwrap_or(ShortcutsAction::None);
+                (settings_action, action)
+            })
+            .and_then(|r| r.inner)
+            .unwrap_or((SettingsAction::None, ShortcutsAction::None));
+        match settings_action {
+            SettingsAction::Changed => {
+                self.settings_changed = true;
+                self.save_current_settings();
+            }
+            SettingsAction::JustForProfile(own) => {
+                if let Some(profile) = self.profiles.active_mut() {
+                    profile.settings = own.then(|| self.settings.clone());
+                    save_profiles(self.storage.as_mut(), &self.profiles);
+                }
+                self.apply_profile_settings();
+            }
+            SettingsAction::None => {}
+        }
+        match action {
+            ShortcutsAction::Rebind(command, chord) => {
+                let taken_by = self.keybindings.command_for(&chord, false).filter(|&c| c != command);
+                if self.keybindings.rebind(command, chord.clone()) {
+                    save_keybindings(self.storage.as_mut(), &self.keybindings);
+                } else if let Some(other) = taken_by {
+                    self.shortcut_editor.message = Some(format!("{} is already used by {}", chord, other.label()));
+                }
+            }
+            ShortcutsAction::ResetDefaults => {
+                self.keybindings = Keybindings::default();
+                save_keybindings(self.storage.as_mut(), &self.keybindings);
+            }
+            ShortcutsAction::None => {}
+        }
+        if !open {
+            self.show_settings = false;
+            self.shortcut_editor.capturing = None;
+            self.resume_after_overlay();
+        }
+    }
+
+    fn render_stats(&mut self, ctx: &egui::Context) {
+        let action = egui::Window::new("Stats")
+            .collapsible(false)
+            .show(ctx, |ui| self.stats_view.render(ui, self.profiles.active(), self.layout(), Utc::now()))
+            .and_then(|r| r.inner)
+            .unwrap_or(StatsAction::None);
+        if action == StatsAction::Close {
+            self.show_stats = false;
+        }
+    }
+
+    fn render_results(&mut self, ctx: &egui::Context) {
+        let action = egui::CentralPanel::default()
+            .show(ctx, |ui| self.results.render(ui))
+            .inner;
+        match action {
+            ResultsAction::RaceAgain => match self.game.mode {
+                GameMode::Lesson { stage } => self.start_lesson(stage),
+                _ => self.start_race(),
+            },
+            ResultsAction::RaceGhost => self.race_chosen_ghost(),
+            ResultsAction::NextPlayer => self.next_hot_seat_player(),
+            ResultsAction::BackToStart => self.game.status = GameStatus::NotStarted,
+            ResultsAction::ShowLeaderboard => self.open_leaderboard(),
+            ResultsAction::None => {}
         }
-        // ... (rest of update logic from main.rs)
     }
 }
 
@@ -31,41 +934,52 @@ impl TyperRacerApp {
         self.top_section.level_seed = self.game.seed.clone();
         self.top_section.timer_seconds = self.game.elapsed;
         // Calculate WPM if the game is running
-        if self.game.status == crate::GameStatus::Running {
+        if self.game.status == GameStatus::Running {
             if self.game.elapsed > 0.0 {
                 // Words per minute calculation (assuming 5 chars per word)
-                let chars_typed = self.game.current_char as f32;
-                let minutes = self.game.elapsed / 60.0;
-                if minutes > 0.0 {
-                    self.top_section.player1_wpm = (chars_typed / 5.0) / minutes;
-                }
+                self.top_section.player1_wpm = self.game.wpm();
             }
             // Update race progress percentage
-            if !self.game.quotes.is_empty() && self.game.current_quote < self.game.quotes.len() {
-                let quote = &self.game.quotes[self.game.current_quote];
-                let total_chars = quote.expanded_meditation.len() as f32;
-                if total_chars > 0.0 {
-                    self.top_section.race_progress_percent = (self.game.current_char as f32 / total_chars) * 100.0;
-                    // Update player view state
-                    self.player1_view.car_position = self.game.current_char as f32 / total_chars;
-                    self.player1_view.errors = self.game.errors;
-                }
-            }
+            let progress = self.game.progress();
+            self.top_section.race_progress_percent = progress * 100.0;
+            // Update player view state
+            self.player1_view.car_position = progress;
+            self.player1_view.errors = self.game.errors;
+        }
+        // Update opponent lane
+        if self.opponent.status != GameStatus::NotStarted {
+            self.opponent.tick(self.clock.now_ms());
+            self.player2_view.car_position = self.opponent.progress();
+            self.player2_view.errors = self.opponent.errors;
+            self.top_section.player2_wpm = self.opponent.wpm();
         }
         // Update text input state
-        if !self.game.quotes.is_empty() && self.game.current_quote < self.game.quotes.len() {
-            let quote = &self.game.quotes[self.game.current_quote];
-            self.text_input.current_quote = quote.expanded_meditation.clone();
-            self.text_input.current_position = self.game.current_char;
-            self.text_input.typed_text = self.typing.buffer.clone();
+        self.text_input.current_quote = self.game.target_text().to_string();
+        self.text_input.current_position = self.game.current_char;
+        self.text_input.typed_text = self.typing.buffer.clone();
+        // Tell screen readers about the word being typed and new WPM highs
+        let word = current_word_range(&self.text_input.current_quote, self.text_input.typed_text.chars().count());
+        let word: String = self.text_input.current_quote.chars().take(word.end).skip(word.start).collect();
+        self.announcer.set_current_word(&word);
+        self.announcer.observe_wpm(self.top_section.player1_wpm);
+        self.text_input.mistakes = self.game.mistakes.clone();
+        self.text_input.extras = self.game.extras.clone();
+        let opponent_racing = self.opponent.status != GameStatus::NotStarted;
+        self.text_input.ghost_position = (self.settings.show_ghost
+            && opponent_racing
+            && self.opponent.target_text() == self.game.target_text())
+        .then_some(self.opponent.current_char);
+        if self.is_split_keyboard() {
+            self.player2_input.current_quote = self.opponent.target_text().to_string();
+            self.player2_input.current_position = self.opponent.current_char;
+            self.player2_input.typed_text = self.opponent.input_buffer.clone();
+            self.player2_input.mistakes = self.opponent.mistakes.clone();
+            self.player2_input.extras = self.opponent.extras.clone();
         }
         // Update footer
-        if self.game.status == crate::GameStatus::Running && self.game.elapsed > 0.0 {
+        if self.game.status == GameStatus::Running && self.game.elapsed > 0.0 {
             // Calculate accuracy
-            let total_chars = self.game.current_char + self.game.errors;
-            if total_chars > 0 {
-                self.footer.accuracy = (self.game.current_char as f32 / total_chars as f32) * 100.0;
-            }
+            self.footer.accuracy = self.game.accuracy();
             // Add current WPM to history every second
             if self.game.elapsed as usize > self.footer.wpm_history.len() {
                 self.footer.wpm_history.push(self.top_section.player1_wpm);
@@ -77,7 +991,6 @@ impl TyperRacerApp {
 
 
 
-use crate::time::TimeInstant;
 #[cfg(target_arch = "wasm32")]
 use crate::TYPER_RACER_ELAPSED;
 
@@ -85,15 +998,26 @@ impl TyperRacerApp {
     /// Create a new app, initializing the timer
     pub fn new() -> Self {
         let mut app = Self::default();
-        app.game.start_time = Some(TimeInstant::now());
+        app.game.quotes = load_expanded_meditations();
+        app.game.status = GameStatus::NotStarted;
+        app.storage = default_storage();
+        app.profiles = load_profiles(app.storage.as_ref());
+        app.leaderboard = load_leaderboard(app.storage.as_ref());
+        app.keybindings = load_keybindings(app.storage.as_ref());
+        app.machine_settings = load_settings(app.storage.as_ref());
+        app.apply_profile_settings();
+        app.themes = available_themes(app.storage.as_ref());
         app
     }
 
     /// Update the timer every frame
     pub fn update_timer(&mut self, ctx: &egui::Context) {
-        if let Some(start_time) = self.game.start_time {
-            let elapsed = start_time.elapsed();
-            self.top_section.timer_seconds = elapsed.as_secs_f32();
+        if self.game.status != GameStatus::Running {
+            return;
+        }
+        if self.game.started_at_ms.is_some() && !self.game.is_paused() {
+            self.game.tick(self.clock.now_ms());
+            self.top_section.timer_seconds = self.game.elapsed;
             #[cfg(target_arch = "wasm32")]
             unsafe {
                 TYPER_RACER_ELAPSED = self.top_section.timer_seconds;
@@ -0,0 +1,99 @@
+// --- Offline replay inspection: per-run summaries and run-to-run diffs ---
+use crate::game::{GameState, GameStatus, MeditationQuote, Replay, BACKSPACE};
+use crate::stats::typed_times;
+
+/// The result a replay earns when played back
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunSummary {
+    pub keystrokes: usize,
+    pub backspaces: usize,
+    pub errors: usize,
+    pub wpm: f32,
+    pub accuracy: f32,
+    /// Seconds from the first key to the last, or the time limit of a timed run
+    pub elapsed: f32,
+    pub finished: bool,
+}
+
+/// Play a replay back through the engine with the clock started at its first key
+pub fn play_replay(quotes: &[MeditationQuote], replay: &Replay) -> GameState {
+    let mut game = GameState::new(quotes.to_vec());
+    let events = replay.active_events();
+    let first_ms = events.first().map(|e| e.milliseconds_since_epoch_utc).unwrap_or(0);
+    game.start_race_at(&replay.config, first_ms);
+    for event in &events {
+        let ms = event.milliseconds_since_epoch_utc;
+        game.tick(ms);
+        game.type_char(event.character, ms);
+    }
+    // A timed run ends when its clock does, whatever was typed last
+    if let Some(limit) = replay.config.mode.time_limit() {
+        game.set_elapsed(limit);
+    }
+    game
+}
+
+pub fn summarize_replay(quotes: &[MeditationQuote], replay: &Replay) -> RunSummary {
+    let game = play_replay(quotes, replay);
+    RunSummary {
+        keystrokes: game.keystrokes,
+        backspaces: replay.events.iter().filter(|e| e.character == BACKSPACE).count(),
+        errors: game.errors,
+        wpm: game.wpm(),
+        accuracy: game.accuracy(),
+        elapsed: game.elapsed,
+        finished: game.status == GameStatus::Finished,
+    }
+}
+
+/// One passage character as typed in two runs. Times are milliseconds from
+/// each run's first key, None where the run never got that far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharDiff {
+    pub index: usize,
+    pub expected: char,
+    pub a_ms: Option<u64>,
+    pub b_ms: Option<u64>,
+    pub a_missed: bool,
+    pub b_missed: bool,
+}
+
+impl CharDiff {
+    /// How much later run B reached this character than run A
+    pub fn delta_ms(&self) -> Option<i64> {
+        Some(self.b_ms? as i64 - self.a_ms? as i64)
+    }
+}
+
+/// Compare two runs of the same race character by character. Returns None
+/// when the replays were typed on different passages.
+pub fn diff_replays(quotes: &[MeditationQuote], a: &Replay, b: &Replay) -> Option<Vec<CharDiff>> {
+    let same_race = a.config.quote_index == b.config.quote_index
+        && a.config.seed == b.config.seed
+        && a.config.mode == b.config.mode;
+    if !same_race {
+        return None;
+    }
+    let relative = |replay: &Replay| {
+        let (game, times) = typed_times(quotes, replay);
+        let first_ms = replay.active_events().first().map(|e| e.milliseconds_since_epoch_utc).unwrap_or(0);
+        let times: Vec<Option<u64>> = times.iter().map(|t| t.map(|ms| ms.saturating_sub(first_ms))).collect();
+        (game, times)
+    };
+    let (game_a, times_a) = relative(a);
+    let (game_b, times_b) = relative(b);
+    let text: Vec<char> = game_a.target_text().chars().collect();
+    let reached = times_a.len().max(times_b.len()).min(text.len());
+    Some(
+        (0..reached)
+            .map(|index| CharDiff {
+                index,
+                expected: text[index],
+                a_ms: times_a.get(index).copied().flatten(),
+                b_ms: times_b.get(index).copied().flatten(),
+                a_missed: game_a.mistakes.contains(&index),
+                b_missed: game_b.mistakes.contains(&index),
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,58 @@
+// Routes input events through an ordered chain of handlers
+use crate::game::{GameState, GameStatus};
+use crate::keyboard::event::{KeyboardEvent, KeyboardEventHandler};
+use crate::versus::{route_split_key, LEFT_BACKSPACE_KEY};
+
+/// Offer `event` to each handler in turn until one handles it. Returns
+/// whether any did.
+pub fn dispatch(event: &KeyboardEvent, chain: &mut [&mut dyn KeyboardEventHandler]) -> bool {
+    chain.iter_mut().any(|handler| handler.handle_keyboard_event(event))
+}
+
+/// The typing engine's link in the chain: typed text and backspace go to
+/// the race, split between both players on a shared keyboard
+pub struct TypingLayer<'a> {
+    pub game: &'a mut GameState,
+    pub opponent: &'a mut GameState,
+    pub split: bool,
+    pub now_ms: u64,
+}
+
+impl KeyboardEventHandler for TypingLayer<'_> {
+    fn handle_keyboard_event(&mut self, event: &KeyboardEvent) -> bool {
+        if self.game.status != GameStatus::Running || self.game.is_paused() {
+            return false;
+        }
+        match event {
+            KeyboardEvent::Text { text } => {
+                for c in text.chars() {
+                    let (left, right) = if self.split {
+                        route_split_key(self.game.layout, c, self.game, self.opponent)
+                    } else {
+                        (true, false)
+                    };
+                    if left {
+                        self.game.type_char(c, self.now_ms);
+                    }
+                    if right {
+                        self.opponent.type_char(c, self.now_ms);
+                    }
+                }
+                true
+            }
+            KeyboardEvent::KeyPress { key, .. } if key == "BACKSPACE" => {
+                if self.split {
+                    self.opponent.backspace(self.now_ms);
+                } else {
+                    self.game.backspace(self.now_ms);
+                }
+                true
+            }
+            KeyboardEvent::KeyPress { key, .. } if self.split && key == LEFT_BACKSPACE_KEY => {
+                self.game.backspace(self.now_ms);
+                true
+            }
+            _ => false,
+        }
+    }
+}
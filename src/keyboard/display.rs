@@ -1,19 +1,280 @@
 // Draws the target text, typed text, blinking cursor, and error highlighting
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
 use eframe::egui;
+use eframe::egui::text::{CCursor, LayoutJob, TextFormat};
+
+use crate::settings::reduced_motion;
+use crate::theme::Theme;
+use crate::ui::helper::motion_seconds;
+
+/// Lines of the passage shown at once around the caret
+pub const VISIBLE_LINES: usize = 3;
+const CARET_ANIMATION_SECONDS: f32 = 0.08;
+const SCROLL_ANIMATION_SECONDS: f32 = 0.2;
+
+/// How one character of the passage should be drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharState {
+    /// Not typed yet
+    Pending,
+    Correct,
+    Incorrect,
+    /// Correct now, but was mistyped at some point
+    Corrected,
+    /// A character typed where the word had already ended, drawn as an
+    /// extra cell between the word and its space
+    Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StyledChar {
+    pub shown: char,
+    pub state: CharState,
+}
+
+/// Overlay the typed text on the passage. Wrong characters show what should
+/// have been typed. Characters typed past the end of a word, from
+/// `GameState::extras`, are inserted as extra cells between the word and
+/// its space.
+pub fn char_states(
+    target: &str,
+    typed: &str,
+    mistakes: &BTreeSet<usize>,
+    extras: &BTreeMap<usize, String>,
+) -> Vec<StyledChar> {
+    let mut typed = typed.chars();
+    let mut cells = Vec::new();
+    for (i, expected) in target.chars().enumerate() {
+        if let Some(extra) = extras.get(&i) {
+            cells.extend(extra.chars().map(|c| StyledChar { shown: c, state: CharState::Extra }));
+        }
+        let cell = match typed.next() {
+            None => StyledChar { shown: expected, state: CharState::Pending },
+            Some(c) if c == expected && mistakes.contains(&i) => {
+                StyledChar { shown: expected, state: CharState::Corrected }
+            }
+            Some(c) if c == expected => StyledChar { shown: expected, state: CharState::Correct },
+            Some(_) => StyledChar { shown: expected, state: CharState::Incorrect },
+        };
+        cells.push(cell);
+    }
+    cells
+}
+
+/// The cell from `char_states` that draws passage character `index`,
+/// counting past any extra cells before it
+pub fn cell_index(cells: &[StyledChar], index: usize) -> usize {
+    cells
+        .iter()
+        .enumerate()
+        .filter(|(_, styled)| styled.state != CharState::Extra)
+        .nth(index)
+        .map(|(cell, _)| cell)
+        .unwrap_or(cells.len())
+}
+
+/// Character range of the word the cursor is in. On a space, that is the
+/// word just typed, since the space is what leaves it.
+pub fn current_word_range(target: &str, cursor: usize) -> Range<usize> {
+    let chars: Vec<char> = target.chars().collect();
+    let cursor = cursor.min(chars.len());
+    let start = chars[..cursor]
+        .iter()
+        .rposition(|c| *c == ' ')
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let end = chars[cursor..]
+        .iter()
+        .position(|c| *c == ' ')
+        .map(|i| cursor + i)
+        .unwrap_or(chars.len());
+    start..end
+}
+
+/// What screen readers call the typing area: the current word and where it
+/// is, e.g. "word 12 of 80: serenity", rather than the whole passage
+pub fn typing_area_label(target: &str, cursor: usize) -> String {
+    let range = current_word_range(target, cursor);
+    let chars: Vec<char> = target.chars().collect();
+    let number = chars[..range.start].iter().filter(|c| **c == ' ').count() + 1;
+    let total = chars.iter().filter(|c| **c == ' ').count() + 1;
+    let word: String = chars[range].iter().collect();
+    format!("word {} of {}: {}", number, total, word)
+}
+
+fn char_format(state: CharState, in_word: bool, font_id: &egui::FontId, theme: &Theme) -> TextFormat {
+    let wrong_background = theme.incorrect.gamma_multiply(0.16);
+    let (color, background) = match state {
+        CharState::Pending => (theme.pending, egui::Color32::TRANSPARENT),
+        CharState::Correct => (theme.correct, egui::Color32::TRANSPARENT),
+        CharState::Incorrect => (theme.incorrect, wrong_background),
+        CharState::Corrected => (theme.corrected, egui::Color32::TRANSPARENT),
+        CharState::Extra => (theme.incorrect.gamma_multiply(0.75), wrong_background),
+    };
+    // Mistakes are struck through and fixed ones slanted, so they read without color
+    let struck = theme.cues && matches!(state, CharState::Incorrect | CharState::Extra);
+    TextFormat {
+        font_id: font_id.clone(),
+        color,
+        background,
+        italics: theme.cues && state == CharState::Corrected,
+        underline: if in_word {
+            egui::Stroke::new(1.5, theme.text)
+        } else {
+            egui::Stroke::NONE
+        },
+        strikethrough: if struck {
+            egui::Stroke::new(1.5, color)
+        } else {
+            egui::Stroke::NONE
+        },
+        ..Default::default()
+    }
+}
 
-pub fn render_typing_area(ui: &mut egui::Ui, target: &str, typed: &str, cursor: usize) {
-    ui.label("Target Text:");
-    ui.monospace(target);
-    ui.add_space(10.0);
-    ui.label("Typed Text:");
-    // Draw typed text with cursor
-    let (before, after) = typed.split_at(cursor.min(typed.len()));
-    ui.horizontal(|ui| {
-        ui.monospace(before);
-        // Blinking cursor
-        if (ui.input(|i| i.time) * 2.0).sin() > 0.0 {
-            ui.monospace("|");
+/// Build the rich text for the passage, one run per change of style
+pub fn typing_layout_job(
+    chars: &[StyledChar],
+    word: Range<usize>,
+    font_id: &egui::FontId,
+    theme: &Theme,
+) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    let mut run = String::new();
+    let mut run_style: Option<(CharState, bool)> = None;
+    for (i, styled) in chars.iter().enumerate() {
+        let style = (styled.state, word.contains(&i));
+        if run_style.is_some_and(|s| s != style) {
+            let (state, in_word) = run_style.unwrap_or(style);
+            job.append(&run, 0.0, char_format(state, in_word, font_id, theme));
+            run.clear();
         }
-        ui.monospace(after);
-    });
+        run_style = Some(style);
+        run.push(styled.shown);
+    }
+    if let Some((state, in_word)) = run_style {
+        job.append(&run, 0.0, char_format(state, in_word, font_id, theme));
+    }
+    job
+}
+
+/// A wrapped view of the passage showing a few lines around the caret,
+/// with the caret (and optionally the opponent's) gliding between glyphs
+pub struct TypingViewport<'a> {
+    target: &'a str,
+    typed: &'a str,
+    mistakes: &'a BTreeSet<usize>,
+    extras: &'a BTreeMap<usize, String>,
+    ghost_cursor: Option<usize>,
+    visible_lines: usize,
+    font_id: egui::FontId,
+}
+
+impl<'a> TypingViewport<'a> {
+    pub fn new(
+        target: &'a str,
+        typed: &'a str,
+        mistakes: &'a BTreeSet<usize>,
+        extras: &'a BTreeMap<usize, String>,
+    ) -> Self {
+        Self {
+            target,
+            typed,
+            mistakes,
+            extras,
+            ghost_cursor: None,
+            visible_lines: VISIBLE_LINES,
+            font_id: egui::FontId::monospace(18.0),
+        }
+    }
+
+    /// Character position of the opponent in the same passage
+    pub fn ghost_cursor(mut self, ghost_cursor: Option<usize>) -> Self {
+        self.ghost_cursor = ghost_cursor;
+        self
+    }
+
+    pub fn visible_lines(mut self, lines: usize) -> Self {
+        self.visible_lines = lines.max(1);
+        self
+    }
+
+    pub fn show(self, ui: &mut egui::Ui) {
+        let id = ui.id().with("typing_viewport");
+        let cursor = self.typed.chars().count();
+        let label = typing_area_label(self.target, cursor);
+        let chars = char_states(self.target, self.typed, self.mistakes, self.extras);
+        // Positions in the passage, moved along by any extra cells
+        let word = current_word_range(self.target, cursor);
+        let word = cell_index(&chars, word.start)..cell_index(&chars, word.end);
+        let cursor = cell_index(&chars, cursor);
+        let theme = Theme::current(ui.ctx());
+        let mut job = typing_layout_job(&chars, word, &self.font_id, &theme);
+        job.wrap.max_width = ui.available_width();
+        let galley = ui.fonts(|f| f.layout_job(job));
+
+        let row_height = galley
+            .rows
+            .first()
+            .map(|r| r.rect.height())
+            .unwrap_or(self.font_id.size);
+        let caret_cursor = galley.from_ccursor(CCursor::new(cursor));
+        // Keep the caret on the second visible line once past the first
+        let last_first_row = galley.rows.len().saturating_sub(self.visible_lines);
+        let first_row = caret_cursor.rcursor.row.saturating_sub(1).min(last_first_row);
+        let scroll_target = galley.rows.get(first_row).map(|r| r.rect.min.y).unwrap_or(0.0);
+
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), row_height * self.visible_lines as f32),
+            egui::Sense::hover(),
+        );
+        // The passage is the value, read only when asked for
+        response.widget_info(|| egui::WidgetInfo {
+            label: Some(label.clone()),
+            current_text_value: Some(self.target.to_string()),
+            ..egui::WidgetInfo::new(egui::WidgetType::Label)
+        });
+        let ctx = ui.ctx().clone();
+        let reduced = reduced_motion(&ctx);
+        let scroll = ctx.animate_value_with_time(id.with("scroll"), scroll_target, motion_seconds(&ctx, SCROLL_ANIMATION_SECONDS));
+        let origin = rect.min - egui::vec2(0.0, scroll);
+        let painter = ui.painter_at(rect);
+        painter.galley(origin, galley.clone(), theme.text);
+
+        let glide = |name: &str, target: egui::Rect| {
+            let glide_time = motion_seconds(&ctx, CARET_ANIMATION_SECONDS);
+            let x = ctx.animate_value_with_time(id.with(name).with("x"), target.min.x, glide_time);
+            let y = ctx.animate_value_with_time(id.with(name).with("y"), target.min.y, glide_time);
+            let top = origin + egui::vec2(x, y);
+            [top, top + egui::vec2(0.0, target.height())]
+        };
+
+        if let Some(ghost) = self.ghost_cursor {
+            let ghost_rect = galley.pos_from_ccursor(CCursor::new(cell_index(&chars, ghost)));
+            painter.line_segment(
+                glide("ghost_caret", ghost_rect),
+                egui::Stroke::new(2.0, theme.ghost),
+            );
+        }
+        let caret = glide("caret", galley.pos_from_cursor(&caret_cursor));
+        // Blinking cursor, steady with reduced motion
+        if reduced || (ui.input(|i| i.time) * 2.0).sin() > 0.0 {
+            painter.line_segment(caret, egui::Stroke::new(2.0, theme.caret));
+        }
+    }
+}
+
+pub fn render_typing_area(
+    ui: &mut egui::Ui,
+    target: &str,
+    typed: &str,
+    mistakes: &BTreeSet<usize>,
+    extras: &BTreeMap<usize, String>,
+    ghost_cursor: Option<usize>,
+) {
+    TypingViewport::new(target, typed, mistakes, extras)
+        .ghost_cursor(ghost_cursor)
+        .show(ui);
 }
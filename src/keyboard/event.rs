@@ -2,10 +2,43 @@
 use eframe::egui;
 use eframe::egui::Key;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum KeyboardEvent {
-    KeyPress { key: String },
+    /// `repeat` is set for presses generated by holding the key down
+    KeyPress { key: String, modifiers: Modifiers, repeat: bool },
     KeyRelease { key: String },
+    /// Characters typed, after the keyboard layout and any IME
+    Text { text: String },
+}
+
+/// Modifier keys held with a key press; `ctrl` is Cmd on macOS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl Modifiers {
+    pub fn from_egui(modifiers: &egui::Modifiers) -> Self {
+        Self { ctrl: modifiers.command, alt: modifiers.alt, shift: modifiers.shift }
+    }
+}
+
+/// The keyboard event for an egui input event, if it is one we route
+pub fn keyboard_event(event: &egui::Event) -> Option<KeyboardEvent> {
+    match event {
+        egui::Event::Text(text) => Some(KeyboardEvent::Text { text: text.clone() }),
+        egui::Event::Key { key, pressed: true, repeat, modifiers, .. } => Some(KeyboardEvent::KeyPress {
+            key: egui_key_to_string(key)?,
+            modifiers: Modifiers::from_egui(modifiers),
+            repeat: *repeat,
+        }),
+        egui::Event::Key { key, pressed: false, .. } => {
+            Some(KeyboardEvent::KeyRelease { key: egui_key_to_string(key)? })
+        }
+        _ => None,
+    }
 }
 
 pub fn egui_key_to_string(key: &Key) -> Option<String> {
@@ -9,7 +9,7 @@ pub struct TypingBuffer {
 impl TypingBuffer {
     pub fn insert_char(&mut self, c: char) {
         self.buffer.insert(self.cursor, c);
-        self.cursor += 1;
+        self.cursor += c.len_utf8();
     }
     pub fn insert_str(&mut self, s: &str) {
         self.buffer.insert_str(self.cursor, s);
@@ -0,0 +1,146 @@
+// Keyboard layouts a player can choose between
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Dvorak,
+    Colemak,
+}
+
+impl KeyboardLayout {
+    pub const ALL: [KeyboardLayout; 3] = [
+        KeyboardLayout::Qwerty,
+        KeyboardLayout::Dvorak,
+        KeyboardLayout::Colemak,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyboardLayout::Qwerty => "QWERTY",
+            KeyboardLayout::Dvorak => "Dvorak",
+            KeyboardLayout::Colemak => "Colemak",
+        }
+    }
+}
+
+/// The finger that should press a key when touch typing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Finger {
+    LeftPinky,
+    LeftRing,
+    LeftMiddle,
+    LeftIndex,
+    Thumb,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+}
+
+impl Finger {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Finger::LeftPinky => "left pinky",
+            Finger::LeftRing => "left ring finger",
+            Finger::LeftMiddle => "left middle finger",
+            Finger::LeftIndex => "left index finger",
+            Finger::Thumb => "thumb",
+            Finger::RightIndex => "right index finger",
+            Finger::RightMiddle => "right middle finger",
+            Finger::RightRing => "right ring finger",
+            Finger::RightPinky => "right pinky",
+        }
+    }
+
+    pub fn is_left_hand(&self) -> bool {
+        matches!(
+            self,
+            Finger::LeftPinky | Finger::LeftRing | Finger::LeftMiddle | Finger::LeftIndex
+        )
+    }
+
+    /// Standard touch-typing assignment by column from the left edge
+    fn for_column(column: usize) -> Finger {
+        match column {
+            0 => Finger::LeftPinky,
+            1 => Finger::LeftRing,
+            2 => Finger::LeftMiddle,
+            3 | 4 => Finger::LeftIndex,
+            5 | 6 => Finger::RightIndex,
+            7 => Finger::RightMiddle,
+            8 => Finger::RightRing,
+            _ => Finger::RightPinky,
+        }
+    }
+}
+
+/// Rows of the main keyboard block, top to bottom
+pub const NUMBER_ROW: usize = 0;
+pub const TOP_ROW: usize = 1;
+pub const HOME_ROW: usize = 2;
+pub const BOTTOM_ROW: usize = 3;
+
+/// Keys typed with shift on a US keyboard, and the key they are shifted from
+const SHIFTED: [(char, char); 21] = [
+    ('!', '1'), ('@', '2'), ('#', '3'), ('$', '4'), ('%', '5'), ('^', '6'), ('&', '7'),
+    ('*', '8'), ('(', '9'), (')', '0'), ('_', '-'), ('+', '='), ('{', '['), ('}', ']'),
+    (':', ';'), ('"', '\''), ('<', ','), ('>', '.'), ('?', '/'), ('~', '`'), ('|', '\\'),
+];
+
+impl KeyboardLayout {
+    /// Unshifted characters of the number, top, home and bottom rows
+    pub fn rows(&self) -> [&'static str; 4] {
+        match self {
+            KeyboardLayout::Qwerty => ["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"],
+            KeyboardLayout::Dvorak => ["1234567890[]", "',.pyfgcrl/=", "aoeuidhtns-", ";qjkxbmwvz"],
+            KeyboardLayout::Colemak => ["1234567890-=", "qwfpgjluy;[]", "arstdhneio'", "zxcvbkm,./"],
+        }
+    }
+
+    /// Row and column of the key that types `c`, ignoring shift
+    pub fn position(&self, c: char) -> Option<(usize, usize)> {
+        let key = unshifted(c);
+        self.rows()
+            .iter()
+            .enumerate()
+            .find_map(|(row, keys)| keys.chars().position(|k| k == key).map(|col| (row, col)))
+    }
+
+    pub fn finger_for(&self, c: char) -> Option<Finger> {
+        if c == ' ' {
+            return Some(Finger::Thumb);
+        }
+        self.position(c).map(|(_, col)| Finger::for_column(col))
+    }
+
+    /// Letters of one row, for lessons built around it
+    pub fn row_letters(&self, row: usize) -> Vec<char> {
+        self.rows()[row].chars().filter(|c| c.is_alphabetic()).collect()
+    }
+
+    /// Letters typed by one hand
+    pub fn hand_letters(&self, left: bool) -> Vec<char> {
+        self.rows()
+            .iter()
+            .flat_map(|keys| keys.chars().enumerate())
+            .filter(|(col, c)| c.is_alphabetic() && Finger::for_column(*col).is_left_hand() == left)
+            .map(|(_, c)| c)
+            .collect()
+    }
+}
+
+/// Whether `c` needs shift held
+pub fn needs_shift(c: char) -> bool {
+    c.is_uppercase() || SHIFTED.iter().any(|(shifted, _)| *shifted == c)
+}
+
+/// The key that types `c` without shift
+pub fn unshifted(c: char) -> char {
+    SHIFTED
+        .iter()
+        .find(|(shifted, _)| *shifted == c)
+        .map(|(_, base)| *base)
+        .unwrap_or_else(|| c.to_lowercase().next().unwrap_or(c))
+}
@@ -4,6 +4,9 @@ pub mod input;
 pub mod onscreen;
 pub mod display;
 pub mod simulator;
+pub mod layout;
+pub mod shortcuts;
+pub mod dispatch;
 // Optionally: pub mod state;
 
 pub use event::*;
@@ -12,3 +15,6 @@ pub use input::*;
 pub use onscreen::*;
 pub use display::*;
 pub use simulator::*;
+pub use layout::*;
+pub use shortcuts::*;
+pub use dispatch::*;
@@ -1,10 +1,113 @@
 // Renders the on-screen keyboard and animates key presses
+use std::collections::BTreeSet;
+
 use eframe::egui;
 
-pub fn render_keyboard(ui: &mut egui::Ui, on_key: &mut dyn FnMut(&str)) {
+use crate::keyboard::layout::{needs_shift, unshifted, KeyboardLayout};
+use crate::stats::{KeyStats, MIN_SAMPLES};
+use crate::ui::helper::{draw_heat_key, draw_key, Marker};
+
+const KEY_SIZE: f32 = 30.0;
+/// How far each row is indented, in key widths, like a physical keyboard
+const ROW_OFFSETS: [f32; 4] = [0.0, 0.5, 0.75, 1.25];
+
+/// How brightly a key being held down is drawn, below the next key's full glow
+const HELD_GLOW: f32 = 0.6;
+
+/// Weakness (see `KeyStats::weakness`) at which a key is marked weak, and
+/// at which it is marked very weak and fully heated
+const WEAK_KEY: f32 = 0.25;
+const VERY_WEAK_KEY: f32 = 0.75;
+
+/// The shape a key with this weakness is marked with, if any
+pub fn weakness_marker(weakness: f32) -> Option<Marker> {
+    if weakness >= VERY_WEAK_KEY {
+        Some(Marker::Triangle)
+    } else if weakness >= WEAK_KEY {
+        Some(Marker::Dot)
+    } else {
+        None
+    }
+}
+
+/// Draw the layout's letter rows with each key heated by how weak it is.
+/// Keys without enough samples stay cold.
+pub fn render_heatmap(ui: &mut egui::Ui, layout: KeyboardLayout, stats: &KeyStats) {
+    let weakness = |key: char| {
+        stats
+            .keys
+            .get(&key)
+            .filter(|s| s.attempts() >= MIN_SAMPLES)
+            .map_or(0.0, |s| stats.weakness(s))
+    };
+    ui.vertical(|ui| {
+        ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
+        for (row, keys) in layout.rows().iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add_space(ROW_OFFSETS[row] * KEY_SIZE);
+                for key in keys.chars() {
+                    let w = weakness(key);
+                    let label = key.to_uppercase().to_string();
+                    draw_heat_key(ui, &label, 1.0, KEY_SIZE, w / VERY_WEAK_KEY, weakness_marker(w));
+                }
+            });
+        }
+    });
+}
+
+/// Draw the layout's keys with the next key to press lit up, and name the
+/// finger that should press it. Keys in `held` glow while they are down.
+/// Clicked keys are passed to `on_key`.
+pub fn render_keyboard(
+    ui: &mut egui::Ui,
+    layout: KeyboardLayout,
+    next_char: Option<char>,
+    held: &BTreeSet<String>,
+    on_key: &mut dyn FnMut(&str),
+) {
+    let next_key = next_char.map(unshifted);
+    let finger = next_char.and_then(|c| layout.finger_for(c));
+    // Shift is pressed by the hand that is not typing the key
+    let shift_left = next_char.is_some_and(needs_shift) && finger.is_some_and(|f| !f.is_left_hand());
+    let shift_right = next_char.is_some_and(needs_shift) && finger.is_some_and(|f| f.is_left_hand());
+    let lit = |on: bool| -> f32 { if on { 1.0 } else { 0.0 } };
+    let glow = |next: bool, name: &str| if held.contains(name) { lit(next).max(HELD_GLOW) } else { lit(next) };
+
     ui.vertical(|ui| {
-        ui.heading("Keyboard");
-        ui.label("[On-screen keyboard visualization goes here]");
-        // TODO: Render real keyboard and handle clicks
+        ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
+        for (row, keys) in layout.rows().iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add_space(ROW_OFFSETS[row] * KEY_SIZE);
+                if row == 3 && draw_key(ui, "Shift", 1.5, KEY_SIZE, lit(shift_left)).clicked() {
+                    on_key("SHIFT");
+                }
+                for key in keys.chars() {
+                    let label = key.to_uppercase().to_string();
+                    if draw_key(ui, &label, 1.0, KEY_SIZE, glow(next_key == Some(key), &label)).clicked() {
+                        on_key(&label);
+                    }
+                }
+                if row == 3 && draw_key(ui, "Shift", 1.5, KEY_SIZE, lit(shift_right)).clicked() {
+                    on_key("SHIFT");
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.add_space(3.0 * KEY_SIZE);
+            if draw_key(ui, "Space", 6.0, KEY_SIZE, glow(next_key == Some(' '), "SPACE")).clicked() {
+                on_key("SPACE");
+            }
+        });
+        if let (Some(c), Some(finger)) = (next_char, finger) {
+            let key = if c == ' ' { "Space".to_string() } else { c.to_string() };
+            let shift = if shift_left {
+                " with left shift"
+            } else if shift_right {
+                " with right shift"
+            } else {
+                ""
+            };
+            ui.label(format!("Next: {} - {}{}", key, finger.name(), shift));
+        }
     });
 }
@@ -1,41 +1,78 @@
-use crate::ReplayEvent;
-
-#[cfg(target_arch = "wasm32")]
-pub fn save_replay(replay: &[ReplayEvent]) {
-    use web_sys::window;
-    if let Ok(json) = serde_json::to_string(replay) {
-        if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
-            let _ = storage.set_item("typer_racer_replay", &json);
+use serde::Deserialize;
+
+use crate::game::{RaceConfig, Replay, ReplayEvent};
+use crate::storage::{join_key, save_json, Storage};
+
+/// Key of the most recent run, raced as the default ghost
+pub const LAST_REPLAY_KEY: &str = "replay";
+/// Namespace holding every named replay
+pub const REPLAYS_NAMESPACE: &str = "replays";
+/// Prefix of the copies kept by "save as ghost", which duplicate a replay
+/// already saved for the race
+pub const GHOST_PREFIX: &str = "ghost-";
+
+/// Replays saved before race configs were recorded are bare event arrays
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredReplay {
+    Full(Replay),
+    Bare(Vec<ReplayEvent>),
+}
+
+/// Parse either replay format. Bare arrays get a default race config on
+/// the passage their keys were typed on.
+pub fn parse_replay(json: &str) -> Option<Replay> {
+    match serde_json::from_str(json).ok()? {
+        StoredReplay::Full(replay) => Some(replay),
+        StoredReplay::Bare(events) => {
+            let quote_index = events.first().map(|e| e.quote_index).unwrap_or(0);
+            let config = RaceConfig { quote_index, ..RaceConfig::default() };
+            Some(Replay { config, events, ..Replay::default() })
         }
     }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub fn save_replay(replay: &[ReplayEvent]) {
-    if let Ok(json) = serde_json::to_string(replay) {
-        let _ = std::fs::write("replay.json", json);
-    }
+/// Whether `json` is a replay in the old bare-array format
+pub fn is_bare_replay(json: &str) -> bool {
+    matches!(serde_json::from_str(json), Ok(StoredReplay::Bare(_)))
 }
 
-#[cfg(target_arch = "wasm32")]
-pub fn load_replay() -> Vec<ReplayEvent> {
-    use web_sys::window;
-    if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
-        if let Ok(Some(json)) = storage.get_item("typer_racer_replay") {
-            serde_json::from_str(&json).unwrap_or_default()
-        } else {
-            Vec::new()
-        }
-    } else {
-        Vec::new()
-    }
+fn load(storage: &dyn Storage, key: &str) -> Replay {
+    storage.get(key).and_then(|json| parse_replay(&json)).unwrap_or_default()
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub fn load_replay() -> Vec<ReplayEvent> {
-    if let Ok(json) = std::fs::read_to_string("replay.json") {
-        serde_json::from_str(&json).unwrap_or_default()
-    } else {
-        Vec::new()
-    }
+pub fn save_replay(storage: &mut dyn Storage, replay: &Replay) -> bool {
+    save_json(storage, LAST_REPLAY_KEY, replay)
+}
+
+pub fn load_replay(storage: &dyn Storage) -> Replay {
+    load(storage, LAST_REPLAY_KEY)
+}
+
+/// Save a replay under its own name so race history can refer back to it
+pub fn save_replay_named(storage: &mut dyn Storage, name: &str, replay: &Replay) -> bool {
+    save_json(storage, &join_key(REPLAYS_NAMESPACE, name), replay)
+}
+
+pub fn load_replay_named(storage: &dyn Storage, name: &str) -> Replay {
+    load(storage, &join_key(REPLAYS_NAMESPACE, name))
+}
+
+/// Name a race's ghost copy is saved under
+pub fn ghost_name(seed: &str) -> String {
+    format!("{}{}", GHOST_PREFIX, seed)
+}
+
+/// Names of all saved replays, leaving out ghost copies so each race is
+/// listed once
+pub fn list_replays(storage: &dyn Storage) -> Vec<String> {
+    storage
+        .list(REPLAYS_NAMESPACE)
+        .into_iter()
+        .filter(|name| !name.starts_with(GHOST_PREFIX))
+        .collect()
+}
+
+pub fn delete_replay(storage: &mut dyn Storage, name: &str) -> bool {
+    storage.delete(&join_key(REPLAYS_NAMESPACE, name))
 }
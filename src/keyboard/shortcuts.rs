@@ -0,0 +1,238 @@
+// --- Keyboard shortcuts: commands, the chords bound to them, and their persistence ---
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::keyboard::event::{KeyboardEvent, KeyboardEventHandler, Modifiers};
+use crate::storage::{load_json, save_json, Storage};
+
+pub const KEYBINDINGS_KEY: &str = "keybindings";
+
+/// Keys that never type a character, so they can be bound without modifiers
+const NON_TYPING_KEYS: [&str; 7] = ["ESC", "INSERT", "DELETE", "LEFT", "RIGHT", "UP", "DOWN"];
+
+/// Something the player can do from a shortcut or the command palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Command {
+    Restart,
+    NextQuote,
+    ToggleGhost,
+    ToggleKeyboard,
+    Pause,
+    OpenSettings,
+    SaveGhost,
+    OpenPalette,
+}
+
+impl Command {
+    pub const ALL: [Command; 8] = [
+        Command::Restart,
+        Command::NextQuote,
+        Command::ToggleGhost,
+        Command::ToggleKeyboard,
+        Command::Pause,
+        Command::OpenSettings,
+        Command::SaveGhost,
+        Command::OpenPalette,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Command::Restart => "Restart race",
+            Command::NextQuote => "Next quote",
+            Command::ToggleGhost => "Toggle ghost caret",
+            Command::ToggleKeyboard => "Toggle on-screen keyboard",
+            Command::Pause => "Pause / resume",
+            Command::OpenSettings => "Open settings",
+            Command::SaveGhost => "Save run as ghost",
+            Command::OpenPalette => "Command palette",
+        }
+    }
+}
+
+/// A key plus the modifiers held with it, e.g. Ctrl+R. Keys use the names
+/// from `egui_key_to_string`, as in `KeyboardEvent::KeyPress`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Chord {
+    pub key: String,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub alt: bool,
+    #[serde(default)]
+    pub shift: bool,
+}
+
+impl Chord {
+    pub fn new(key: &str) -> Self {
+        Self { key: key.to_string(), ctrl: false, alt: false, shift: false }
+    }
+
+    pub fn ctrl(key: &str) -> Self {
+        Self { ctrl: true, ..Self::new(key) }
+    }
+
+    pub fn alt(key: &str) -> Self {
+        Self { alt: true, ..Self::new(key) }
+    }
+
+    pub fn from_press(key: &str, modifiers: Modifiers) -> Self {
+        Self { key: key.to_string(), ctrl: modifiers.ctrl, alt: modifiers.alt, shift: modifiers.shift }
+    }
+
+    /// Parse the form `Display` writes, e.g. "Ctrl+Shift+P"
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key = parts.pop().filter(|k| !k.is_empty())?;
+        let mut chord = Self::new(&key.to_uppercase());
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "cmd" => chord.ctrl = true,
+                "alt" => chord.alt = true,
+                "shift" => chord.shift = true,
+                _ => return None,
+            }
+        }
+        Some(chord)
+    }
+
+    /// Whether pressing this could never be mistaken for typing. Shift
+    /// alone does not count: it types capitals.
+    pub fn is_safe_while_racing(&self) -> bool {
+        self.ctrl || self.alt || NON_TYPING_KEYS.contains(&self.key.as_str())
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Which chord runs each command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keybindings {
+    pub bindings: BTreeMap<Command, Chord>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let bindings = Command::ALL.iter().map(|&c| (c, default_chord(c))).collect();
+        Self { bindings }
+    }
+}
+
+fn default_chord(command: Command) -> Chord {
+    match command {
+        Command::Restart => command_chord("R"),
+        Command::NextQuote => command_chord("N"),
+        Command::ToggleGhost => command_chord("G"),
+        Command::ToggleKeyboard => command_chord("K"),
+        Command::Pause => Chord::new("ESC"),
+        Command::OpenSettings => command_chord("O"),
+        Command::SaveGhost => command_chord("S"),
+        Command::OpenPalette => command_chord("P"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn command_chord(key: &str) -> Chord {
+    Chord::ctrl(key)
+}
+
+/// Browsers keep Ctrl+R, Ctrl+N, Ctrl+P and the like for reload, new
+/// window and print, so the web build defaults to Alt
+#[cfg(target_arch = "wasm32")]
+fn command_chord(key: &str) -> Chord {
+    Chord::alt(key)
+}
+
+impl Keybindings {
+    pub fn chord(&self, command: Command) -> Option<&Chord> {
+        self.bindings.get(&command)
+    }
+
+    /// The command a chord runs. While racing, chords that could be typing
+    /// are ignored so they reach the passage instead.
+    pub fn command_for(&self, chord: &Chord, racing: bool) -> Option<Command> {
+        if racing && !chord.is_safe_while_racing() {
+            return None;
+        }
+        self.bindings.iter().find(|(_, c)| *c == chord).map(|(&command, _)| command)
+    }
+
+    /// Bind a command to a new chord, returning false if another command
+    /// already uses it
+    pub fn rebind(&mut self, command: Command, chord: Chord) -> bool {
+        if self.bindings.iter().any(|(&other, c)| other != command && *c == chord) {
+            return false;
+        }
+        self.bindings.insert(command, chord);
+        true
+    }
+}
+
+/// First link of the input chain: claims key presses bound to a command
+/// and collects the commands for the caller to run
+pub struct ShortcutLayer<'a> {
+    pub keybindings: &'a Keybindings,
+    pub racing: bool,
+    /// A text field or overlay has the keyboard, so only chords with Ctrl
+    /// or Alt count
+    pub text_focus: bool,
+    pub fired: Vec<Command>,
+}
+
+impl<'a> ShortcutLayer<'a> {
+    pub fn new(keybindings: &'a Keybindings, racing: bool, text_focus: bool) -> Self {
+        Self { keybindings, racing, text_focus, fired: Vec::new() }
+    }
+}
+
+impl KeyboardEventHandler for ShortcutLayer<'_> {
+    fn handle_keyboard_event(&mut self, event: &KeyboardEvent) -> bool {
+        let KeyboardEvent::KeyPress { key, modifiers, repeat: false } = event else {
+            return false;
+        };
+        if self.text_focus && !modifiers.ctrl && !modifiers.alt {
+            return false;
+        }
+        let chord = Chord::from_press(key, *modifiers);
+        let Some(command) = self.keybindings.command_for(&chord, self.racing) else {
+            return false;
+        };
+        self.fired.push(command);
+        true
+    }
+}
+
+/// Commands whose label contains `query`, ignoring case, in menu order
+pub fn matching_commands(query: &str) -> Vec<Command> {
+    let query = query.trim().to_lowercase();
+    Command::ALL
+        .into_iter()
+        .filter(|c| c.label().to_lowercase().contains(&query))
+        .collect()
+}
+
+/// Saved bindings, with defaults for any command missing from them
+pub fn load_keybindings(storage: &dyn Storage) -> Keybindings {
+    let saved: BTreeMap<Command, Chord> = load_json(storage, KEYBINDINGS_KEY);
+    let mut keybindings = Keybindings::default();
+    keybindings.bindings.extend(saved);
+    keybindings
+}
+
+pub fn save_keybindings(storage: &mut dyn Storage, keybindings: &Keybindings) -> bool {
+    save_json(storage, KEYBINDINGS_KEY, &keybindings.bindings)
+}
@@ -1,12 +1,22 @@
-use crate::keyboard::event::KeyboardEvent;
+use crate::keyboard::event::{KeyboardEvent, Modifiers};
+use crate::game::BACKSPACE;
+use crate::time::Clock;
 use crate::ReplayEvent;
 
 /// Simulates replaying keyboard events with timing.
 pub struct KeyboardSimulator {
     pub events: Vec<ReplayEvent>,
     pub current_index: usize,
-    pub start_time: Option<u64>, // milliseconds since epoch
+    pub start_time: Option<u64>, // clock reading in milliseconds when playback began
     pub running: bool,
+    /// Clock reading when playback was paused
+    pub paused_at: Option<u64>,
+}
+
+impl Default for KeyboardSimulator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl KeyboardSimulator {
@@ -16,37 +26,59 @@ impl KeyboardSimulator {
             current_index: 0,
             start_time: None,
             running: false,
+            paused_at: None,
         }
     }
 
-    /// Start a new replay
-    pub fn start_replay(&mut self, events: Vec<ReplayEvent>, start_time: u64) {
+    /// Start a new replay from `clock`'s current reading
+    pub fn start_replay(&mut self, events: Vec<ReplayEvent>, clock: &dyn Clock) {
         self.events = events;
         self.current_index = 0;
-        self.start_time = Some(start_time);
+        self.start_time = Some(clock.now_ms());
         self.running = true;
+        self.paused_at = None;
     }
 
-    /// Advance the simulation; returns Some(KeyboardEvent) if it's time to emit one
-    pub fn tick(&mut self, now_ms: u64) -> Option<KeyboardEvent> {
-        if !self.running || self.start_time.is_none() || self.current_index >= self.events.len() {
-            return None;
+    /// Hold playback until `resume`
+    pub fn pause(&mut self, clock: &dyn Clock) {
+        if self.running && self.paused_at.is_none() {
+            self.paused_at = Some(clock.now_ms());
         }
-        let base_time = self.start_time.unwrap();
-        while self.current_index < self.events.len() {
-            let event = &self.events[self.current_index];
-            if event.milliseconds_since_epoch_utc <= now_ms {
-                // Convert ReplayEvent to KeyboardEvent (assuming character replay)
-                let key_event = KeyboardEvent::KeyPress { key: event.character.to_string() };
-                self.current_index += 1;
-                return Some(key_event);
-            } else {
-                break;
-            }
+    }
+
+    /// Carry on from where playback was paused
+    pub fn resume(&mut self, clock: &dyn Clock) {
+        if let (Some(paused_at), Some(start_time)) = (self.paused_at.take(), self.start_time) {
+            self.start_time = Some(start_time + clock.now_ms().saturating_sub(paused_at));
+        }
+    }
+
+    /// Advance the simulation; returns Some(KeyboardEvent) if it's time to emit one.
+    /// Events are replayed at their original spacing, offset from the first
+    /// recorded event, so call this in a loop to drain everything that is due.
+    pub fn tick(&mut self, clock: &dyn Clock) -> Option<KeyboardEvent> {
+        if !self.running || self.paused_at.is_some() {
+            return None;
         }
         // Stop if done
         if self.current_index >= self.events.len() {
             self.running = false;
+            return None;
+        }
+        let start_time = self.start_time?;
+        let first = self.events[0].milliseconds_since_epoch_utc;
+        let event = &self.events[self.current_index];
+        let due = event.milliseconds_since_epoch_utc.saturating_sub(first);
+        if due <= clock.now_ms().saturating_sub(start_time) {
+            // Convert ReplayEvent to KeyboardEvent, naming backspace like egui_key_to_string
+            let key = if event.character == BACKSPACE {
+                "BACKSPACE".to_string()
+            } else {
+                event.character.to_string()
+            };
+            let key_event = KeyboardEvent::KeyPress { key, modifiers: Modifiers::default(), repeat: false };
+            self.current_index += 1;
+            return Some(key_event);
         }
         None
     }
@@ -0,0 +1,130 @@
+// --- Local Leaderboard across all profiles ---
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::game::RaceConfig;
+use crate::mode::GameMode;
+use crate::policy::ErrorPolicy;
+use crate::storage::{load_json, save_json, Storage};
+
+pub const LEADERBOARD_KEY: &str = "leaderboard";
+
+/// Name of the only quote pack shipped today
+pub const DEFAULT_PACK: &str = "expanded-meditations";
+
+/// Runs kept for each pack, quote and mode; slower runs are dropped
+pub const MAX_ENTRIES: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub player: String,
+    pub date: DateTime<Utc>,
+    pub pack: String,
+    pub quote_index: usize,
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub policy: ErrorPolicy,
+    pub seed: String,
+    pub wpm: f32,
+    pub accuracy: f32,
+    /// Name the replay was saved under, so the run can be raced as a ghost
+    pub replay_ref: String,
+}
+
+/// Which runs to rank; `None` matches everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LeaderboardFilter {
+    pub pack: Option<String>,
+    pub quote_index: Option<usize>,
+    pub mode: Option<GameMode>,
+}
+
+impl LeaderboardFilter {
+    pub fn matches(&self, entry: &LeaderboardEntry) -> bool {
+        self.pack.as_ref().is_none_or(|p| p == &entry.pack)
+            && self.quote_index.is_none_or(|q| q == entry.quote_index)
+            && self.mode.is_none_or(|m| m == entry.mode)
+    }
+}
+
+impl LeaderboardEntry {
+    /// The race this run was recorded on, for racing it again as a ghost
+    pub fn config(&self) -> RaceConfig {
+        RaceConfig {
+            quote_index: self.quote_index,
+            seed: self.seed.clone(),
+            mode: self.mode,
+            policy: self.policy,
+            ..RaceConfig::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+/// Best first: net WPM, then accuracy, then earliest date
+fn best_first(a: &LeaderboardEntry, b: &LeaderboardEntry) -> Ordering {
+    b.wpm
+        .total_cmp(&a.wpm)
+        .then(b.accuracy.total_cmp(&a.accuracy))
+        .then(a.date.cmp(&b.date))
+}
+
+impl Leaderboard {
+    /// Record a run, keeping only the best `MAX_ENTRIES` on its pack, quote
+    /// and mode
+    pub fn add(&mut self, entry: LeaderboardEntry) {
+        let group = LeaderboardFilter {
+            pack: Some(entry.pack.clone()),
+            quote_index: Some(entry.quote_index),
+            mode: Some(entry.mode),
+        };
+        self.entries.push(entry);
+        self.entries.sort_by(best_first);
+        let mut kept = 0;
+        self.entries.retain(|e| {
+            if !group.matches(e) {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_ENTRIES
+        });
+    }
+
+    /// Matching runs, best first: net WPM, then accuracy, then earliest date
+    pub fn ranked(&self, filter: &LeaderboardFilter) -> Vec<&LeaderboardEntry> {
+        let mut ranked: Vec<&LeaderboardEntry> =
+            self.entries.iter().filter(|e| filter.matches(e)).collect();
+        ranked.sort_by(|a, b| best_first(a, b));
+        ranked
+    }
+
+    /// Distinct game modes that have at least one run
+    pub fn modes(&self) -> Vec<GameMode> {
+        GameMode::all()
+            .into_iter()
+            .filter(|m| self.entries.iter().any(|e| e.mode == *m))
+            .collect()
+    }
+
+    /// Distinct quote packs that have at least one run
+    pub fn packs(&self) -> Vec<String> {
+        let mut packs: Vec<String> = self.entries.iter().map(|e| e.pack.clone()).collect();
+        packs.sort();
+        packs.dedup();
+        packs
+    }
+}
+
+pub fn save_leaderboard(storage: &mut dyn Storage, board: &Leaderboard) -> bool {
+    save_json(storage, LEADERBOARD_KEY, board)
+}
+
+pub fn load_leaderboard(storage: &dyn Storage) -> Leaderboard {
+    load_json(storage, LEADERBOARD_KEY)
+}
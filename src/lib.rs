@@ -1,10 +1,47 @@
 use wasm_bindgen::prelude::*;
 
-// Import the TYPER_RACER_ELAPSED global from main.rs
-extern "C" {
-    #[link_name = "TYPER_RACER_ELAPSED"]
-    static TYPER_RACER_ELAPSED: f32;
-}
+pub mod keyboard;
+pub mod ui;
+pub mod quote_loader;
+pub mod rng;
+pub mod game;
+pub mod mode;
+pub mod policy;
+pub mod profile;
+pub mod leaderboard;
+pub mod storage;
+pub mod settings;
+pub mod theme;
+pub mod time;
+pub mod events;
+pub mod a11y;
+pub mod audio;
+pub mod stats;
+pub mod drill;
+pub mod curriculum;
+pub mod review;
+pub mod versus;
+pub mod net;
+pub mod verify;
+pub mod inspect;
+pub mod sim;
+
+mod impl_typer_racer_app;
+mod typer_racer_app;
+
+pub use typer_racer_app::TyperRacerApp;
+pub use game::{MeditationQuote, RaceConfig, Replay, ReplayEvent, GameState, GameStatus};
+pub use mode::GameMode;
+pub use policy::ErrorPolicy;
+pub use ui::sections;
+
+#[cfg(test)]
+mod tests;
+
+// --- WASM Timer Global ---
+// Written by the app every frame, read back from JavaScript
+#[no_mangle]
+pub static mut TYPER_RACER_ELAPSED: f32 = 0.0;
 
 /// Returns the elapsed time for the typer racer game
 /// This function is exported to JavaScript
@@ -13,15 +50,17 @@ pub fn get_elapsed_time() -> f32 {
     unsafe { TYPER_RACER_ELAPSED }
 }
 
-// Initialize the panic hook for better error messages in the browser console
-#[wasm_bindgen(start)]
+/// Initialize the panic hook and browser logging.
+/// The trunk binary entrypoint in main.rs does its own setup, so this is
+/// only for hosts that load the library directly through wasm-pack.
+#[wasm_bindgen]
 pub fn start() {
     // Set up panic hook for better error messages
     console_error_panic_hook::set_once();
-    
+
     // Initialize logging to browser console
     #[cfg(target_arch = "wasm32")]
-    console_log::init_with_level(log::Level::Debug).expect("Failed to initialize logger");
-    
+    let _ = console_log::init_with_level(log::Level::Debug);
+
     log::info!("Typer Racer WebAssembly module initialized");
 }
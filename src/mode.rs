@@ -0,0 +1,115 @@
+// --- Game Modes and the passages they generate ---
+use serde::{Deserialize, Serialize};
+
+use crate::curriculum::LessonStage;
+use crate::game::MeditationQuote;
+use crate::rng::SeededRng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Type the whole passage; fastest finish wins
+    #[default]
+    Race,
+    /// Type words streamed from the quote packs until the clock runs out
+    Timed { seconds: u32 },
+    /// Type a fixed number of words drawn from the quote packs
+    WordCount { words: usize },
+    /// Untimed run through a passage with no ghost
+    Practice,
+    /// Words chosen to exercise the player's weakest keys and bigrams
+    Drill,
+    /// One stage of the touch-typing lesson track
+    Lesson { stage: LessonStage },
+    /// A short round of problem words that are due for review
+    WarmUp,
+    /// One player's half of a split-keyboard race
+    HalfKeyboard { left: bool },
+}
+
+impl GameMode {
+    pub const TIMED_SECONDS: [u32; 4] = [15, 30, 60, 120];
+    pub const WORD_COUNTS: [usize; 4] = [10, 25, 50, 100];
+
+    /// Every mode offered on the start screen
+    pub fn all() -> Vec<GameMode> {
+        let mut modes = vec![GameMode::Race];
+        modes.extend(Self::TIMED_SECONDS.iter().map(|&seconds| GameMode::Timed { seconds }));
+        modes.extend(Self::WORD_COUNTS.iter().map(|&words| GameMode::WordCount { words }));
+        modes.push(GameMode::Practice);
+        modes.push(GameMode::Drill);
+        modes
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            GameMode::Race => "Race".to_string(),
+            GameMode::Timed { seconds } => format!("Timed {}s", seconds),
+            GameMode::WordCount { words } => format!("{} Words", words),
+            GameMode::Practice => "Practice".to_string(),
+            GameMode::Drill => "Adaptive Drill".to_string(),
+            GameMode::Lesson { stage } => format!("Lesson: {}", stage.label()),
+            GameMode::WarmUp => "Warm-up".to_string(),
+            GameMode::HalfKeyboard { left: true } => "Left Hand".to_string(),
+            GameMode::HalfKeyboard { left: false } => "Right Hand".to_string(),
+        }
+    }
+
+    pub fn time_limit(&self) -> Option<f32> {
+        match self {
+            GameMode::Timed { seconds } => Some(*seconds as f32),
+            _ => None,
+        }
+    }
+
+    /// Whether the race screen shows the elapsed-time clock; every mode but
+    /// practice does. Use `time_limit` to tell whether the race is timed.
+    pub fn shows_clock(&self) -> bool {
+        !matches!(self, GameMode::Practice)
+    }
+
+    pub fn allows_ghost(&self) -> bool {
+        !matches!(self, GameMode::Practice)
+    }
+
+    /// Only races, timed runs and word counts are comparable between
+    /// players; the other modes never reach the leaderboard
+    pub fn is_ranked(&self) -> bool {
+        matches!(self, GameMode::Race | GameMode::Timed { .. } | GameMode::WordCount { .. })
+    }
+}
+
+impl std::fmt::Display for GameMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.label())
+    }
+}
+
+/// Words drawn at random from every loaded quote, reproducible from the seed
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordStream {
+    rng: SeededRng,
+}
+
+impl WordStream {
+    /// Words a timed race starts with; more are appended as the player nears the end
+    pub const TIMED_CHUNK: usize = 60;
+
+    pub fn new(seed: &str) -> Self {
+        Self { rng: SeededRng::from_seed(seed) }
+    }
+
+    /// The next `count` words joined by single spaces
+    pub fn next_words(&mut self, quotes: &[MeditationQuote], count: usize) -> String {
+        let words: Vec<&str> = quotes
+            .iter()
+            .flat_map(|q| q.expanded_meditation.split_whitespace())
+            .collect();
+        if words.is_empty() {
+            return String::new();
+        }
+        (0..count)
+            .map(|_| words[self.rng.index(words.len())])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
@@ -0,0 +1,231 @@
+// Connection to a race server. Native builds run the socket on a
+// background thread; browser builds use the page's WebSocket.
+use crate::net::protocol::{decode, encode, ClientMessage, ServerMessage};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::cell::RefCell;
+    use std::io::ErrorKind;
+    use std::net::TcpStream;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tungstenite::stream::MaybeTlsStream;
+    use tungstenite::{Message, WebSocket};
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    pub struct Socket {
+        outgoing: Sender<String>,
+        /// Received messages, or why the connection couldn't be made
+        incoming: Receiver<Result<String, String>>,
+        open: Arc<AtomicBool>,
+        failure: RefCell<Option<String>>,
+    }
+
+    impl Socket {
+        /// Connect on a background thread, so a slow or missing server
+        /// doesn't hold up the UI. Messages sent meanwhile wait for the
+        /// connection.
+        pub fn open(url: &str) -> Option<Self> {
+            let (outgoing, to_send) = channel();
+            let (received, incoming) = channel();
+            let open = Arc::new(AtomicBool::new(true));
+            let still_open = open.clone();
+            let url = url.to_string();
+            std::thread::spawn(move || {
+                match connect(&url) {
+                    Ok(socket) => run(socket, to_send, received),
+                    Err(error) => {
+                        let _ = received.send(Err(format!("Could not connect to {}: {}", url, error)));
+                    }
+                }
+                still_open.store(false, Ordering::Relaxed);
+            });
+            Some(Self { outgoing, incoming, open, failure: RefCell::new(None) })
+        }
+
+        pub fn send(&self, text: String) -> bool {
+            self.outgoing.send(text).is_ok()
+        }
+
+        pub fn receive(&self) -> Vec<String> {
+            let mut texts = Vec::new();
+            for message in self.incoming.try_iter() {
+                match message {
+                    Ok(text) => texts.push(text),
+                    Err(failure) => *self.failure.borrow_mut() = Some(failure),
+                }
+            }
+            texts
+        }
+
+        pub fn is_open(&self) -> bool {
+            self.open.load(Ordering::Relaxed)
+        }
+
+        /// Why the connection couldn't be made, once `receive` has seen it
+        pub fn failure(&self) -> Option<String> {
+            self.failure.borrow().clone()
+        }
+    }
+
+    fn connect(url: &str) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, String> {
+        let (socket, _) = tungstenite::connect(url).map_err(|e| e.to_string())?;
+        if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+            stream.set_read_timeout(Some(POLL_INTERVAL)).map_err(|e| e.to_string())?;
+        }
+        Ok(socket)
+    }
+
+    fn run(
+        mut socket: WebSocket<MaybeTlsStream<TcpStream>>,
+        to_send: Receiver<String>,
+        received: Sender<Result<String, String>>,
+    ) {
+        loop {
+            loop {
+                match to_send.try_recv() {
+                    Ok(text) => {
+                        if socket.send(Message::text(text)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        let _ = socket.close(None);
+                        let _ = socket.flush();
+                        return;
+                    }
+                }
+            }
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    if received.send(Ok(text.to_string())).is_err() {
+                        return;
+                    }
+                }
+                Ok(Message::Close(_)) => return,
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    use wasm_bindgen::prelude::*;
+    use web_sys::{MessageEvent, WebSocket};
+
+    pub struct Socket {
+        socket: WebSocket,
+        incoming: Rc<RefCell<VecDeque<String>>>,
+        /// Messages sent before the connection opened
+        pending: RefCell<Vec<String>>,
+        _on_message: Closure<dyn FnMut(MessageEvent)>,
+    }
+
+    impl Socket {
+        pub fn open(url: &str) -> Option<Self> {
+            let socket = WebSocket::new(url).ok()?;
+            let incoming = Rc::new(RefCell::new(VecDeque::new()));
+            let inbox = incoming.clone();
+            let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                if let Some(text) = event.data().as_string() {
+                    inbox.borrow_mut().push_back(text);
+                }
+            });
+            socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            Some(Self {
+                socket,
+                incoming,
+                pending: RefCell::new(Vec::new()),
+                _on_message: on_message,
+            })
+        }
+
+        fn flush(&self) {
+            if self.socket.ready_state() != WebSocket::OPEN {
+                return;
+            }
+            for text in self.pending.borrow_mut().drain(..) {
+                let _ = self.socket.send_with_str(&text);
+            }
+        }
+
+        pub fn send(&self, text: String) -> bool {
+            if self.socket.ready_state() > WebSocket::OPEN {
+                return false;
+            }
+            self.pending.borrow_mut().push(text);
+            self.flush();
+            true
+        }
+
+        pub fn receive(&self) -> Vec<String> {
+            self.flush();
+            self.incoming.borrow_mut().drain(..).collect()
+        }
+
+        pub fn is_open(&self) -> bool {
+            self.socket.ready_state() <= WebSocket::OPEN
+        }
+
+        /// The browser doesn't say why a WebSocket failed
+        pub fn failure(&self) -> Option<String> {
+            None
+        }
+    }
+
+    impl Drop for Socket {
+        fn drop(&mut self) {
+            self.socket.set_onmessage(None);
+            let _ = self.socket.close();
+        }
+    }
+}
+
+/// A connection to a race server
+pub struct NetClient {
+    socket: backend::Socket,
+}
+
+impl NetClient {
+    /// Connect to a server URL such as `ws://127.0.0.1:9001`
+    pub fn connect(url: &str) -> Option<Self> {
+        backend::Socket::open(url).map(|socket| Self { socket })
+    }
+
+    pub fn send(&self, message: &ClientMessage) -> bool {
+        self.socket.send(encode(message))
+    }
+
+    /// Messages received since the last poll
+    pub fn poll(&self) -> Vec<ServerMessage> {
+        self.socket.receive().iter().filter_map(|text| decode(text)).collect()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.socket.is_open()
+    }
+
+    /// Why connecting failed, if it did and the reason is known
+    pub fn failure(&self) -> Option<String> {
+        self.socket.failure()
+    }
+}
+
+impl std::fmt::Debug for NetClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("NetClient")
+    }
+}
@@ -0,0 +1,11 @@
+// Online races: the wire protocol, the race server's lobby logic and a
+// client that works in both native and browser builds
+pub mod protocol;
+pub mod server;
+pub mod client;
+pub mod session;
+
+pub use protocol::*;
+pub use server::*;
+pub use client::*;
+pub use session::*;
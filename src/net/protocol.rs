@@ -0,0 +1,77 @@
+// Messages exchanged between race clients and the server, sent as JSON text frames
+use serde::{Deserialize, Serialize};
+
+use crate::game::RaceConfig;
+
+pub type PlayerId = u32;
+
+/// Port the reference server listens on by default
+pub const DEFAULT_PORT: u16 = 9001;
+pub const DEFAULT_SERVER_URL: &str = "ws://127.0.0.1:9001";
+/// How long clients count down before a race starts
+pub const COUNTDOWN_MS: u64 = 3000;
+/// Longest countdown a client will wait through, whatever the server asks
+pub const MAX_COUNTDOWN_MS: u64 = 4 * COUNTDOWN_MS;
+/// Longest seed a client accepts from a server
+pub const MAX_SEED_LEN: usize = 64;
+/// How often racers report their progress
+pub const PROGRESS_INTERVAL_MS: u64 = 250;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    Join { name: String },
+    /// Toggle whether this player is ready for the next race
+    Ready { ready: bool },
+    Progress { chars: usize, total: usize, wpm: f32 },
+    Finish { wpm: f32, accuracy: f32, elapsed: f32 },
+    Leave,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LobbyPlayer {
+    pub id: PlayerId,
+    pub name: String,
+    pub ready: bool,
+}
+
+/// A player's final result, in finishing order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    pub id: PlayerId,
+    pub name: String,
+    pub wpm: f32,
+    pub accuracy: f32,
+    pub elapsed: f32,
+    /// False for players who left before finishing
+    pub finished: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    Welcome { id: PlayerId },
+    Lobby { players: Vec<LobbyPlayer> },
+    /// Everyone is ready: count down, then race `config`
+    RaceStart { config: RaceConfig, countdown_ms: u64 },
+    Progress { id: PlayerId, chars: usize, total: usize, wpm: f32 },
+    Finished { id: PlayerId, place: usize },
+    RaceOver { standings: Vec<Standing> },
+    Error { message: String },
+}
+
+pub fn encode<T: Serialize>(message: &T) -> String {
+    serde_json::to_string(message).unwrap_or_default()
+}
+
+pub fn decode<T: for<'de> Deserialize<'de>>(text: &str) -> Option<T> {
+    serde_json::from_str(text).ok()
+}
+
+/// Seeds end up in storage keys, so a server may only send letters, digits,
+/// '-' and '_'
+pub fn is_safe_seed(seed: &str) -> bool {
+    !seed.is_empty()
+        && seed.len() <= MAX_SEED_LEN
+        && seed.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
@@ -0,0 +1,206 @@
+// Lobby and race bookkeeping for the race server, independent of sockets
+use std::collections::BTreeMap;
+
+use crate::game::{quote_for_seed, RaceConfig};
+use crate::net::protocol::{ClientMessage, LobbyPlayer, PlayerId, ServerMessage, Standing, COUNTDOWN_MS};
+
+/// Players allowed in one race unless the server is told otherwise
+pub const DEFAULT_MAX_PLAYERS: usize = 8;
+
+/// Who a server message is for
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recipient {
+    All,
+    Player(PlayerId),
+}
+
+pub type Outbox = Vec<(Recipient, ServerMessage)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RacePhase {
+    #[default]
+    Lobby,
+    Racing,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Racer {
+    name: String,
+    ready: bool,
+    /// Set once the player has joined with a name
+    joined: bool,
+    result: Option<Standing>,
+}
+
+/// One lobby that races together. Feed it connection events and client
+/// messages; it answers with the messages to send.
+#[derive(Debug, Clone)]
+pub struct RaceServer {
+    racers: BTreeMap<PlayerId, Racer>,
+    next_id: PlayerId,
+    max_players: usize,
+    quote_count: usize,
+    phase: RacePhase,
+    /// Finishers in order, plus anyone who left mid-race
+    standings: Vec<Standing>,
+}
+
+impl RaceServer {
+    pub fn new(quote_count: usize, max_players: usize) -> Self {
+        Self {
+            racers: BTreeMap::new(),
+            next_id: 1,
+            max_players: max_players.max(1),
+            quote_count,
+            phase: RacePhase::Lobby,
+            standings: Vec::new(),
+        }
+    }
+
+    pub fn phase(&self) -> RacePhase {
+        self.phase
+    }
+
+    /// A new connection. Returns its id, or None when the lobby is full.
+    pub fn connect(&mut self) -> Option<PlayerId> {
+        if self.racers.len() >= self.max_players {
+            return None;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.racers.insert(id, Racer::default());
+        Some(id)
+    }
+
+    pub fn disconnect(&mut self, id: PlayerId) -> Outbox {
+        let Some(racer) = self.racers.remove(&id) else {
+            return Vec::new();
+        };
+        let mut outbox = Vec::new();
+        if self.phase == RacePhase::Racing && racer.joined && racer.result.is_none() {
+            self.standings.push(Standing {
+                id,
+                name: racer.name,
+                wpm: 0.0,
+                accuracy: 0.0,
+                elapsed: 0.0,
+                finished: false,
+            });
+        }
+        outbox.push((Recipient::All, self.lobby()));
+        self.check_race_over(&mut outbox);
+        outbox
+    }
+
+    /// Handle one message. `now_ms` seeds the next race.
+    pub fn handle(&mut self, id: PlayerId, message: ClientMessage, now_ms: u64) -> Outbox {
+        let mut outbox = Vec::new();
+        let phase = self.phase;
+        let Some(racer) = self.racers.get_mut(&id) else {
+            return outbox;
+        };
+        match message {
+            ClientMessage::Join { name } => {
+                let name = name.trim();
+                if name.is_empty() {
+                    outbox.push((Recipient::Player(id), error("Choose a name to join")));
+                    return outbox;
+                }
+                racer.name = name.to_string();
+                racer.joined = true;
+                outbox.push((Recipient::Player(id), ServerMessage::Welcome { id }));
+                outbox.push((Recipient::All, self.lobby()));
+            }
+            ClientMessage::Ready { ready } => {
+                if !racer.joined || phase != RacePhase::Lobby {
+                    return outbox;
+                }
+                racer.ready = ready;
+                outbox.push((Recipient::All, self.lobby()));
+                self.start_if_ready(now_ms, &mut outbox);
+            }
+            ClientMessage::Progress { chars, total, wpm } => {
+                if phase == RacePhase::Racing && racer.result.is_none() {
+                    outbox.push((Recipient::All, ServerMessage::Progress { id, chars, total, wpm }));
+                }
+            }
+            ClientMessage::Finish { wpm, accuracy, elapsed } => {
+                if phase != RacePhase::Racing || racer.result.is_some() || !racer.ready {
+                    return outbox;
+                }
+                let standing = Standing {
+                    id,
+                    name: racer.name.clone(),
+                    wpm,
+                    accuracy,
+                    elapsed,
+                    finished: true,
+                };
+                racer.result = Some(standing.clone());
+                self.standings.push(standing);
+                let place = self.standings.iter().filter(|s| s.finished).count();
+                outbox.push((Recipient::All, ServerMessage::Finished { id, place }));
+                self.check_race_over(&mut outbox);
+            }
+            ClientMessage::Leave => return self.disconnect(id),
+        }
+        outbox
+    }
+
+    pub fn lobby(&self) -> ServerMessage {
+        ServerMessage::Lobby {
+            players: self
+                .racers
+                .iter()
+                .filter(|(_, r)| r.joined)
+                .map(|(id, r)| LobbyPlayer { id: *id, name: r.name.clone(), ready: r.ready })
+                .collect(),
+        }
+    }
+
+    /// Start once every joined player is ready
+    fn start_if_ready(&mut self, now_ms: u64, outbox: &mut Outbox) {
+        let joined: Vec<&Racer> = self.racers.values().filter(|r| r.joined).collect();
+        if joined.is_empty() || !joined.iter().all(|r| r.ready) {
+            return;
+        }
+        let seed = format!("online-{}", now_ms);
+        let quote_index = quote_for_seed(&seed, self.quote_count);
+        self.phase = RacePhase::Racing;
+        self.standings.clear();
+        for racer in self.racers.values_mut() {
+            racer.result = None;
+        }
+        outbox.push((
+            Recipient::All,
+            ServerMessage::RaceStart {
+                config: RaceConfig { quote_index, seed, ..RaceConfig::default() },
+                countdown_ms: COUNTDOWN_MS,
+            },
+        ));
+    }
+
+    /// End the race once every racer has a result, and reopen the lobby
+    fn check_race_over(&mut self, outbox: &mut Outbox) {
+        if self.phase != RacePhase::Racing {
+            return;
+        }
+        let racing = self.racers.values().any(|r| r.joined && r.ready && r.result.is_none());
+        if racing {
+            return;
+        }
+        self.phase = RacePhase::Lobby;
+        for racer in self.racers.values_mut() {
+            racer.ready = false;
+        }
+        let mut standings = std::mem::take(&mut self.standings);
+        // Finishers in order, then anyone who left
+        standings.sort_by_key(|s| !s.finished);
+        outbox.push((Recipient::All, ServerMessage::RaceOver { standings }));
+        outbox.push((Recipient::All, self.lobby()));
+    }
+}
+
+fn error(message: &str) -> ServerMessage {
+    ServerMessage::Error { message: message.to_string() }
+}
@@ -0,0 +1,173 @@
+// The client's view of an online lobby and the race in progress
+use std::collections::BTreeMap;
+
+use crate::game::{GameState, RaceConfig};
+use crate::net::client::NetClient;
+use crate::net::protocol::{
+    is_safe_seed, ClientMessage, LobbyPlayer, PlayerId, ServerMessage, Standing, PROGRESS_INTERVAL_MS,
+};
+
+/// Another player's car in an online race
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RemoteRacer {
+    pub name: String,
+    pub chars: usize,
+    pub total: usize,
+    pub wpm: f32,
+    pub place: Option<usize>,
+}
+
+impl RemoteRacer {
+    pub fn progress(&self) -> f32 {
+        if self.total > 0 {
+            self.chars as f32 / self.total as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+/// What the app needs to act on after a server message
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEvent {
+    /// Count down, then start `config`
+    Countdown { config: RaceConfig, countdown_ms: u64 },
+    RaceOver,
+}
+
+#[derive(Debug, Default)]
+pub struct OnlineSession {
+    client: Option<NetClient>,
+    pub id: Option<PlayerId>,
+    pub lobby: Vec<LobbyPlayer>,
+    /// Everyone else in the current race
+    pub racers: BTreeMap<PlayerId, RemoteRacer>,
+    /// Results of the last race
+    pub standings: Vec<Standing>,
+    pub last_error: Option<String>,
+    /// Whether this player is in the race under way
+    pub racing: bool,
+    last_progress_ms: u64,
+}
+
+impl OnlineSession {
+    /// Start connecting and ask to join. A failure to connect turns up in
+    /// `last_error` on a later `poll`.
+    pub fn connect(&mut self, url: &str, name: &str) -> bool {
+        self.disconnect();
+        match NetClient::connect(url) {
+            Some(client) => {
+                client.send(&ClientMessage::Join { name: name.to_string() });
+                self.client = Some(client);
+                self.last_error = None;
+                true
+            }
+            None => {
+                self.last_error = Some(format!("Could not connect to {}", url));
+                false
+            }
+        }
+    }
+
+    pub fn disconnect(&mut self) {
+        if let Some(client) = self.client.take() {
+            client.send(&ClientMessage::Leave);
+        }
+        *self = Self {
+            last_error: self.last_error.take(),
+            ..Self::default()
+        };
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.as_ref().is_some_and(|c| c.is_open())
+    }
+
+    pub fn send(&self, message: &ClientMessage) -> bool {
+        self.client.as_ref().is_some_and(|c| c.send(message))
+    }
+
+    /// Whether this player is marked ready in the lobby
+    pub fn is_ready(&self) -> bool {
+        self.lobby.iter().any(|p| Some(p.id) == self.id && p.ready)
+    }
+
+    /// Handle everything the server sent since the last frame
+    pub fn poll(&mut self) -> Vec<SessionEvent> {
+        let messages = self.client.as_ref().map(|c| c.poll()).unwrap_or_default();
+        let events = messages.into_iter().filter_map(|m| self.apply(m)).collect();
+        if let Some(client) = self.client.as_ref().filter(|c| !c.is_open()) {
+            let error = client.failure().unwrap_or_else(|| "Disconnected from the server".to_string());
+            self.last_error = Some(error);
+            self.disconnect();
+        }
+        events
+    }
+
+    pub fn apply(&mut self, message: ServerMessage) -> Option<SessionEvent> {
+        match message {
+            ServerMessage::Welcome { id } => self.id = Some(id),
+            ServerMessage::Lobby { players } => self.lobby = players,
+            ServerMessage::RaceStart { config, countdown_ms } => {
+                // Only players who were ready take part, and only in a race
+                // whose seed is safe to save under
+                if !self.is_ready() || !is_safe_seed(&config.seed) {
+                    return None;
+                }
+                self.racing = true;
+                self.standings.clear();
+                self.racers = self
+                    .lobby
+                    .iter()
+                    .filter(|p| p.ready && Some(p.id) != self.id)
+                    .map(|p| (p.id, RemoteRacer { name: p.name.clone(), ..RemoteRacer::default() }))
+                    .collect();
+                return Some(SessionEvent::Countdown { config, countdown_ms });
+            }
+            ServerMessage::Progress { id, chars, total, wpm } => {
+                if let Some(racer) = self.racers.get_mut(&id) {
+                    racer.chars = chars;
+                    racer.total = total;
+                    racer.wpm = wpm;
+                }
+            }
+            ServerMessage::Finished { id, place } => {
+                if let Some(racer) = self.racers.get_mut(&id) {
+                    racer.place = Some(place);
+                    racer.chars = racer.total;
+                }
+            }
+            ServerMessage::RaceOver { standings } => {
+                let was_racing = self.racing;
+                self.racing = false;
+                self.standings = standings;
+                return was_racing.then_some(SessionEvent::RaceOver);
+            }
+            ServerMessage::Error { message } => self.last_error = Some(message),
+        }
+        None
+    }
+
+    /// Send this player's progress, at most every `PROGRESS_INTERVAL_MS`
+    pub fn report_progress(&mut self, game: &GameState, now_ms: u64) {
+        if !self.racing || now_ms < self.last_progress_ms + PROGRESS_INTERVAL_MS {
+            return;
+        }
+        self.last_progress_ms = now_ms;
+        self.send(&ClientMessage::Progress {
+            chars: game.current_char,
+            total: game.target_text().chars().count(),
+            wpm: game.wpm(),
+        });
+    }
+
+    pub fn report_finish(&mut self, game: &GameState) {
+        if self.racing {
+            self.send(&ClientMessage::Finish {
+                wpm: game.wpm(),
+                accuracy: game.accuracy(),
+                elapsed: game.elapsed,
+            });
+        }
+    }
+}
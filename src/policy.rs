@@ -0,0 +1,44 @@
+// --- Error Policies: what happens when a wrong key is pressed ---
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ErrorPolicy {
+    /// The cursor waits on a character until the right key is pressed
+    #[default]
+    StopOnError,
+    /// Mistakes advance the cursor and are marked; backspace to fix them
+    Forgiving,
+    /// Like forgiving, but space will not leave a word that has mistakes
+    WordStrict,
+    /// Mistakes advance the cursor and backspace does nothing
+    NoBackspace,
+}
+
+impl ErrorPolicy {
+    pub const ALL: [ErrorPolicy; 4] = [
+        ErrorPolicy::StopOnError,
+        ErrorPolicy::Forgiving,
+        ErrorPolicy::WordStrict,
+        ErrorPolicy::NoBackspace,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorPolicy::StopOnError => "Stop on error",
+            ErrorPolicy::Forgiving => "Forgiving",
+            ErrorPolicy::WordStrict => "Word strict",
+            ErrorPolicy::NoBackspace => "No backspace",
+        }
+    }
+
+    /// Whether a wrong character still moves the cursor forward
+    pub fn advances_on_error(&self) -> bool {
+        !matches!(self, ErrorPolicy::StopOnError)
+    }
+
+    /// Whether backspace may remove typed characters. Stop-on-error never
+    /// leaves a mistake behind, so there is nothing to fix.
+    pub fn allows_backspace(&self) -> bool {
+        matches!(self, ErrorPolicy::Forgiving | ErrorPolicy::WordStrict)
+    }
+}
@@ -0,0 +1,246 @@
+// --- Player Profiles and Race History ---
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::curriculum::{LessonProgress, LessonStage};
+use crate::drill::{next_level, MIN_LEVEL};
+use crate::game::{GameState, Replay};
+use crate::keyboard::layout::KeyboardLayout;
+use crate::mode::GameMode;
+use crate::review::{review_key, ReviewSchedule};
+use crate::settings::Settings;
+use crate::stats::{analyze_replay, word_attempts, KeyStats};
+use crate::storage::{load_json, save_json, Storage};
+
+pub const PROFILES_KEY: &str = "profiles";
+
+/// One completed race in a player's history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RaceRecord {
+    pub date: DateTime<Utc>,
+    pub quote_index: usize,
+    pub seed: String,
+    #[serde(default)]
+    pub mode: GameMode,
+    pub wpm: f32,
+    pub accuracy: f32,
+    /// Name the replay was saved under, see `keyboard::replay::load_replay_named`
+    pub replay_ref: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredProfile")]
+pub struct Profile {
+    pub name: String,
+    /// This player's own settings, used instead of the machine's while
+    /// they are playing. None follows the machine's settings.
+    pub settings: Option<Settings>,
+    pub history: Vec<RaceRecord>,
+    /// Per-key results accumulated over every race
+    pub key_stats: KeyStats,
+    pub drill_level: u8,
+    pub lessons: LessonProgress,
+    /// Problem words and when to practise them again
+    pub reviews: ReviewSchedule,
+}
+
+/// Display preferences as profiles saved them before `Settings` existed
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct LegacyProfileSettings {
+    ui_scale: f32,
+    dark_mode: bool,
+    show_ghost: bool,
+    show_keyboard: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum StoredSettings {
+    Legacy(LegacyProfileSettings),
+    Current(Settings),
+}
+
+/// A profile as saved by this or an older version
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct StoredProfile {
+    name: String,
+    #[serde(default)]
+    settings: Option<StoredSettings>,
+    /// Saved beside the legacy settings; now `Settings::layout`
+    #[serde(default)]
+    preferred_layout: Option<KeyboardLayout>,
+    #[serde(default)]
+    history: Vec<RaceRecord>,
+    #[serde(default)]
+    key_stats: KeyStats,
+    #[serde(default = "default_drill_level")]
+    drill_level: u8,
+    #[serde(default)]
+    lessons: LessonProgress,
+    #[serde(default)]
+    reviews: ReviewSchedule,
+}
+
+impl From<StoredProfile> for Profile {
+    fn from(stored: StoredProfile) -> Self {
+        let settings = match stored.settings {
+            Some(StoredSettings::Current(settings)) => Some(settings.clamped()),
+            Some(StoredSettings::Legacy(legacy)) => Some(
+                Settings {
+                    theme: if legacy.dark_mode { "Dark" } else { "Light" }.to_string(),
+                    ui_scale: legacy.ui_scale,
+                    show_ghost: legacy.show_ghost,
+                    show_keyboard: legacy.show_keyboard,
+                    layout: stored.preferred_layout.unwrap_or_default(),
+                    ..Settings::default()
+                }
+                .clamped(),
+            ),
+            None => stored.preferred_layout.map(|layout| Settings { layout, ..Settings::default() }),
+        };
+        Self {
+            name: stored.name,
+            settings,
+            history: stored.history,
+            key_stats: stored.key_stats,
+            drill_level: stored.drill_level,
+            lessons: stored.lessons,
+            reviews: stored.reviews,
+        }
+    }
+}
+
+fn default_drill_level() -> u8 {
+    MIN_LEVEL
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            settings: None,
+            history: Vec::new(),
+            key_stats: KeyStats::default(),
+            drill_level: MIN_LEVEL,
+            lessons: LessonProgress::default(),
+            reviews: ReviewSchedule::default(),
+        }
+    }
+
+    pub fn best_wpm(&self) -> Option<f32> {
+        self.history.iter().map(|r| r.wpm).reduce(f32::max)
+    }
+}
+
+/// All profiles on this machine plus which one is playing
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileStore {
+    pub profiles: Vec<Profile>,
+    pub active: Option<String>,
+}
+
+impl ProfileStore {
+    /// Add a new profile and make it active. Returns false if the name is
+    /// empty or already taken, or has `/`, `\` or `..` in it, since names
+    /// end up in storage keys.
+    pub fn create(&mut self, name: &str) -> bool {
+        let name = name.trim();
+        let unsafe_name = name.contains(['/', '\\']) || name.contains("..");
+        if name.is_empty() || unsafe_name || self.profiles.iter().any(|p| p.name == name) {
+            return false;
+        }
+        self.profiles.push(Profile::new(name));
+        self.active = Some(name.to_string());
+        true
+    }
+
+    pub fn select(&mut self, name: &str) -> bool {
+        if self.profiles.iter().any(|p| p.name == name) {
+            self.active = Some(name.to_string());
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn active(&self) -> Option<&Profile> {
+        let name = self.active.as_ref()?;
+        self.profiles.iter().find(|p| &p.name == name)
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut Profile> {
+        let name = self.active.clone()?;
+        self.profiles.iter_mut().find(|p| p.name == name)
+    }
+
+    /// Learn from a finished race: review words, drill level, lesson progress,
+    /// and the race itself in history and key stats. A warm-up is the player's
+    /// worst words on purpose, so it only updates their reviews. Returns the
+    /// lesson stage raced and whether it is now passed.
+    pub fn record_finished(
+        &mut self,
+        game: &GameState,
+        replay: &Replay,
+        replay_ref: &str,
+    ) -> Option<(LessonStage, bool)> {
+        let now = Utc::now();
+        let (wpm, accuracy) = (game.wpm(), game.accuracy());
+        let warm_up = game.mode == GameMode::WarmUp;
+        let profile = self.active_mut()?;
+        if !warm_up {
+            profile.key_stats.merge(&analyze_replay(&game.quotes, replay));
+        }
+        for attempt in word_attempts(&game.quotes, replay) {
+            let word = review_key(&attempt.word);
+            if word.is_empty() {
+                continue;
+            }
+            if attempt.mistyped || attempt.slow {
+                profile.reviews.log_failure(&word, now);
+            } else {
+                profile.reviews.log_success(&word, now);
+            }
+        }
+        if game.mode == GameMode::Drill {
+            profile.drill_level = next_level(profile.drill_level, accuracy);
+        }
+        let lesson = match game.mode {
+            GameMode::Lesson { stage } => {
+                profile.lessons.record(stage, wpm, accuracy);
+                Some((stage, profile.lessons.passed(stage)))
+            }
+            _ => None,
+        };
+        if !warm_up {
+            self.record_race(RaceRecord {
+                date: now,
+                quote_index: game.current_quote,
+                seed: game.seed.clone(),
+                mode: game.mode,
+                wpm,
+                accuracy,
+                replay_ref: replay_ref.to_string(),
+            });
+        }
+        lesson
+    }
+
+    /// Append a finished race to the active profile's history
+    pub fn record_race(&mut self, record: RaceRecord) -> bool {
+        match self.active_mut() {
+            Some(profile) => {
+                profile.history.push(record);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub fn save_profiles(storage: &mut dyn Storage, store: &ProfileStore) -> bool {
+    save_json(storage, PROFILES_KEY, store)
+}
+
+pub fn load_profiles(storage: &dyn Storage) -> ProfileStore {
+    load_json(storage, PROFILES_KEY)
+}
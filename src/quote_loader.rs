@@ -1,8 +1,15 @@
 use crate::MeditationQuote;
 
+/// Where the bundled passages live, relative to the working directory
+pub const DEFAULT_QUOTES_PATH: &str = "level/expanded-meditations.json";
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn load_expanded_meditations() -> Vec<MeditationQuote> {
-    let path = "level/expanded-meditations.json";
+    load_expanded_meditations_from(DEFAULT_QUOTES_PATH)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_expanded_meditations_from(path: &str) -> Vec<MeditationQuote> {
     let mut file = match std::fs::File::open(path) {
         Ok(f) => f,
         Err(_) => return vec![],
@@ -12,7 +19,12 @@ pub fn load_expanded_meditations() -> Vec<MeditationQuote> {
     if file.read_to_string(&mut contents).is_err() {
         return vec![];
     }
-    let parsed: serde_json::Value = match serde_json::from_str(&contents) {
+    parse_expanded_meditations(&contents)
+}
+
+/// Passages from the quote pack's JSON, or none if it doesn't parse
+pub fn parse_expanded_meditations(contents: &str) -> Vec<MeditationQuote> {
+    let parsed: serde_json::Value = match serde_json::from_str(contents) {
         Ok(val) => val,
         Err(_) => return vec![],
     };
@@ -28,10 +40,9 @@ pub fn load_expanded_meditations() -> Vec<MeditationQuote> {
     }).collect()
 }
 
+/// The browser can't read the pack from disk, so it is built in. Quote
+/// indices then match the race server's, which reads the same file.
 #[cfg(target_arch = "wasm32")]
 pub fn load_expanded_meditations() -> Vec<MeditationQuote> {
-    vec![MeditationQuote {
-        original_quotes: vec!["Practice makes perfect.".to_string()],
-        expanded_meditation: "Focus on steady improvement.".to_string(),
-    }]
+    parse_expanded_meditations(include_str!("../level/expanded-meditations.json"))
 }
@@ -0,0 +1,113 @@
+// --- Spaced repetition of words the player keeps fumbling ---
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+const START_EASE: f32 = 2.5;
+const MIN_EASE: f32 = 1.3;
+/// Due words typed in one warm-up round
+pub const WARM_UP_WORDS: usize = 8;
+/// Each due word is typed this many times in the warm-up
+const WARM_UP_REPEATS: usize = 2;
+
+/// A problem word and when it should next be practised (SM-2 style)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewCard {
+    pub word: String,
+    pub due: DateTime<Utc>,
+    pub interval_days: f32,
+    pub ease: f32,
+    /// Clean runs since the word was last fumbled
+    pub repetitions: u32,
+    /// Times the word was fumbled
+    pub lapses: u32,
+}
+
+impl ReviewCard {
+    fn new(word: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            word: word.to_string(),
+            due: now,
+            interval_days: 0.0,
+            ease: START_EASE,
+            repetitions: 0,
+            lapses: 0,
+        }
+    }
+
+    /// Fumbled again: start over and make the word come back sooner
+    fn lapse(&mut self, now: DateTime<Utc>) {
+        self.lapses += 1;
+        self.repetitions = 0;
+        self.interval_days = 0.0;
+        self.ease = (self.ease - 0.2).max(MIN_EASE);
+        self.due = now;
+    }
+
+    /// Typed cleanly while due: push the next review further out
+    fn pass(&mut self, now: DateTime<Utc>) {
+        self.repetitions += 1;
+        self.interval_days = match self.repetitions {
+            1 => 1.0,
+            2 => 6.0,
+            _ => self.interval_days * self.ease,
+        };
+        self.ease += 0.1;
+        self.due = now + Duration::seconds((self.interval_days * 86_400.0) as i64);
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReviewSchedule {
+    pub cards: BTreeMap<String, ReviewCard>,
+}
+
+impl ReviewSchedule {
+    /// Record a word typed with mistakes or unusually slowly
+    pub fn log_failure(&mut self, word: &str, now: DateTime<Utc>) {
+        self.cards
+            .entry(word.to_string())
+            .or_insert_with(|| ReviewCard::new(word, now))
+            .lapse(now);
+    }
+
+    /// Record a clean, quick run of a word. Only due words move on, so
+    /// typing a word many times in one day counts once.
+    pub fn log_success(&mut self, word: &str, now: DateTime<Utc>) {
+        if let Some(card) = self.cards.get_mut(word).filter(|c| c.due <= now) {
+            card.pass(now);
+        }
+    }
+
+    /// Up to `limit` due words, most overdue first
+    pub fn due(&self, now: DateTime<Utc>, limit: usize) -> Vec<String> {
+        let mut due: Vec<&ReviewCard> = self.cards.values().filter(|c| c.due <= now).collect();
+        due.sort_by(|a, b| a.due.cmp(&b.due).then(b.lapses.cmp(&a.lapses)));
+        due.into_iter().take(limit).map(|c| c.word.clone()).collect()
+    }
+
+    /// Cards ordered by when they are next due
+    pub fn upcoming(&self) -> Vec<&ReviewCard> {
+        let mut cards: Vec<&ReviewCard> = self.cards.values().collect();
+        cards.sort_by(|a, b| a.due.cmp(&b.due).then(a.word.cmp(&b.word)));
+        cards
+    }
+}
+
+/// Text of a warm-up round over the given words
+pub fn warm_up_text(words: &[String]) -> String {
+    words
+        .iter()
+        .flat_map(|w| std::iter::repeat_n(w.as_str(), WARM_UP_REPEATS))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The form a word is scheduled under: lowercase letters only
+pub fn review_key(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
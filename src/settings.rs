@@ -0,0 +1,140 @@
+// --- Settings: preferences for this machine, applied to egui and saved with everything else ---
+use std::collections::BTreeSet;
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::Sound;
+use crate::keyboard::layout::KeyboardLayout;
+use crate::policy::ErrorPolicy;
+use crate::storage::{load_json, save_json, Storage};
+use crate::theme::ColorVision;
+
+pub const SETTINGS_KEY: &str = "settings";
+
+pub const MIN_UI_SCALE: f32 = 0.5;
+pub const MAX_UI_SCALE: f32 = 3.0;
+pub const MIN_FONT_SIZE: f32 = 8.0;
+pub const MAX_FONT_SIZE: f32 = 32.0;
+pub const MAX_COUNTDOWN_SECS: u32 = 10;
+
+/// Which recorded run "Race ghost" puts in the other lane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GhostChoice {
+    /// The run that just finished
+    #[default]
+    LastRun,
+    /// The player's fastest run on the same passage and mode
+    PersonalBest,
+}
+
+impl GhostChoice {
+    pub const ALL: [GhostChoice; 2] = [GhostChoice::LastRun, GhostChoice::PersonalBest];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GhostChoice::LastRun => "Last run",
+            GhostChoice::PersonalBest => "Personal best",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Name of a built-in or saved theme, see `theme::available_themes`
+    pub theme: String,
+    /// Feedback colors are swapped for ones that stay distinct for this vision
+    pub color_vision: ColorVision,
+    /// Mark errors and weak keys with shapes and patterns, not just color
+    pub cues: bool,
+    /// Zoom applied to the whole UI
+    pub ui_scale: f32,
+    /// Body text size in points; headings and small text follow it
+    pub font_size: f32,
+    pub layout: KeyboardLayout,
+    pub policy: ErrorPolicy,
+    /// Master switch for sound effects
+    pub sound: bool,
+    /// Sound effect volume, 0.0 to 1.0
+    pub volume: f32,
+    /// Sound effects turned off one by one
+    pub muted_sounds: BTreeSet<Sound>,
+    pub ghost: GhostChoice,
+    pub show_ghost: bool,
+    pub show_keyboard: bool,
+    /// No caret blink, key fades, caret glides or road scrolling
+    pub reduced_motion: bool,
+    /// Read race announcements aloud in the web build. Desktop screen
+    /// readers get them through AccessKit either way.
+    pub screen_reader: bool,
+    /// Seconds counted down before a race starts, 0 to start at once
+    pub countdown_secs: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: "Dark".to_string(),
+            color_vision: ColorVision::default(),
+            cues: false,
+            ui_scale: 1.0,
+            font_size: 14.0,
+            layout: KeyboardLayout::default(),
+            policy: ErrorPolicy::default(),
+            sound: true,
+            volume: 0.6,
+            muted_sounds: BTreeSet::new(),
+            ghost: GhostChoice::default(),
+            show_ghost: true,
+            show_keyboard: true,
+            reduced_motion: false,
+            screen_reader: false,
+            countdown_secs: 3,
+        }
+    }
+}
+
+impl Settings {
+    /// Pull hand-edited or outdated values back into range
+    pub fn clamped(mut self) -> Self {
+        self.ui_scale = self.ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+        self.font_size = self.font_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+        self.countdown_secs = self.countdown_secs.min(MAX_COUNTDOWN_SECS);
+        self.volume = self.volume.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set egui's zoom, text sizes, animation and screen reader options to
+    /// match. The theme is installed separately, see `theme::Theme::install`.
+    pub fn apply(&self, ctx: &egui::Context) {
+        ctx.set_zoom_factor(self.ui_scale);
+        ctx.options_mut(|o| o.screen_reader = self.screen_reader);
+        ctx.data_mut(|d| d.insert_temp(egui::Id::NULL.with("reduced_motion"), self.reduced_motion));
+        let size = self.font_size;
+        let reduced_motion = self.reduced_motion;
+        ctx.style_mut(|style| {
+            style.animation_time = if reduced_motion { 0.0 } else { egui::Style::default().animation_time };
+            for (text_style, font) in style.text_styles.iter_mut() {
+                font.size = match text_style {
+                    egui::TextStyle::Heading => size * 1.4,
+                    egui::TextStyle::Small => size * 0.75,
+                    _ => size,
+                };
+            }
+        });
+    }
+}
+
+/// Whether the applied settings ask for reduced motion
+pub fn reduced_motion(ctx: &egui::Context) -> bool {
+    ctx.data(|d| d.get_temp(egui::Id::NULL.with("reduced_motion"))).unwrap_or(false)
+}
+
+pub fn load_settings(storage: &dyn Storage) -> Settings {
+    load_json::<Settings>(storage, SETTINGS_KEY).clamped()
+}
+
+pub fn save_settings(storage: &mut dyn Storage, settings: &Settings) -> bool {
+    save_json(storage, SETTINGS_KEY, settings)
+}
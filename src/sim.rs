@@ -0,0 +1,199 @@
+// --- Headless races on a virtual clock, for regression tests and tooling ---
+use crate::game::{GameState, GameStatus, MeditationQuote, RaceConfig, ReplayEvent};
+use crate::rng::SeededRng;
+
+/// Default spacing of timeline frames
+pub const DEFAULT_STEP_MS: u64 = 100;
+/// A race that runs longer than this is cut off
+pub const MAX_RACE_MS: u64 = 30 * 60 * 1000;
+
+/// Where a simulated racer's keys come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Typist {
+    /// Recorded keys, replayed at their original spacing from the start
+    Replay(Vec<ReplayEvent>),
+    /// A scripted typist at an uneven but steady pace that sometimes slips
+    Bot { wpm: f32, accuracy: f32, seed: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimRacer {
+    pub name: String,
+    pub typist: Typist,
+}
+
+/// One racer at one frame of the timeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct RacerSnapshot {
+    pub current_char: usize,
+    /// Car position along the road, 0.0 to 1.0
+    pub progress: f32,
+    pub wpm: f32,
+    pub errors: usize,
+    pub finished: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimFrame {
+    pub time_ms: u64,
+    pub racers: Vec<RacerSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimResult {
+    pub name: String,
+    pub wpm: f32,
+    pub accuracy: f32,
+    pub errors: usize,
+    pub keystrokes: usize,
+    pub elapsed: f32,
+    pub finished: bool,
+    /// Keys the racer typed, timed on the virtual clock
+    pub replay: Vec<ReplayEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimOutcome {
+    pub timeline: Vec<SimFrame>,
+    pub results: Vec<SimResult>,
+}
+
+/// Feeds one racer's keys in time order
+struct Driver {
+    typist: Typist,
+    next: usize,
+    /// Bot state: when its next key lands and its random source
+    next_at: u64,
+    rng: SeededRng,
+}
+
+impl Driver {
+    fn new(typist: &Typist) -> Self {
+        let seed = match typist {
+            Typist::Bot { seed, .. } => seed.as_str(),
+            Typist::Replay(_) => "",
+        };
+        let mut driver = Self { typist: typist.clone(), next: 0, next_at: 0, rng: SeededRng::from_seed(seed) };
+        driver.next_at = driver.bot_gap();
+        driver
+    }
+
+    /// Milliseconds until the bot's next key, jittered by up to a quarter
+    fn bot_gap(&mut self) -> u64 {
+        let Typist::Bot { wpm, .. } = self.typist else {
+            return 0;
+        };
+        let ms_per_key = 12_000.0 / wpm.max(1.0);
+        (ms_per_key * (0.75 + self.rng.next_f32() * 0.5)) as u64
+    }
+
+    /// The next key due at or before `until_ms`, with its time
+    fn next_key(&mut self, game: &GameState, until_ms: u64) -> Option<(u64, char)> {
+        match &self.typist {
+            Typist::Replay(events) => {
+                let first = events.first()?.milliseconds_since_epoch_utc;
+                let event = events.get(self.next)?;
+                let at = event.milliseconds_since_epoch_utc.saturating_sub(first);
+                if at > until_ms {
+                    return None;
+                }
+                self.next += 1;
+                Some((at, event.character))
+            }
+            Typist::Bot { accuracy, .. } => {
+                let accuracy = *accuracy;
+                if self.next_at > until_ms || game.status != GameStatus::Running {
+                    return None;
+                }
+                let expected = game.target_text().chars().nth(game.current_char)?;
+                let slip = self.rng.next_f32() * 100.0 >= accuracy;
+                let key = if slip { slipped_key(expected) } else { expected };
+                let at = self.next_at;
+                self.next_at += self.bot_gap();
+                Some((at, key))
+            }
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        match &self.typist {
+            Typist::Replay(events) => self.next >= events.len(),
+            Typist::Bot { .. } => false,
+        }
+    }
+}
+
+/// A wrong key for a bot to press instead of `expected`
+fn slipped_key(expected: char) -> char {
+    if expected == 'x' {
+        'z'
+    } else {
+        'x'
+    }
+}
+
+/// Run a whole race with no UI. The virtual clock starts at 0 and advances
+/// in `step_ms` frames until every racer is finished or out of keys. The
+/// same inputs always give the same outcome.
+pub fn simulate_race(
+    quotes: &[MeditationQuote],
+    config: &RaceConfig,
+    racers: &[SimRacer],
+    step_ms: u64,
+) -> SimOutcome {
+    let step_ms = step_ms.max(1);
+    let mut games: Vec<GameState> = racers
+        .iter()
+        .map(|_| {
+            let mut game = GameState::new(quotes.to_vec());
+            game.start_race_at(config, 0);
+            game
+        })
+        .collect();
+    let mut drivers: Vec<Driver> = racers.iter().map(|r| Driver::new(&r.typist)).collect();
+    let mut timeline = Vec::new();
+    let mut now = 0;
+    loop {
+        for (game, driver) in games.iter_mut().zip(drivers.iter_mut()) {
+            while let Some((at, key)) = driver.next_key(game, now) {
+                game.tick(at);
+                game.type_char(key, at);
+            }
+            game.tick(now);
+        }
+        timeline.push(SimFrame { time_ms: now, racers: games.iter().map(snapshot).collect() });
+        let racing = games
+            .iter()
+            .zip(&drivers)
+            .any(|(game, driver)| game.status == GameStatus::Running && !driver.is_done());
+        if !racing || now >= MAX_RACE_MS {
+            break;
+        }
+        now += step_ms;
+    }
+    let results = racers
+        .iter()
+        .zip(&games)
+        .map(|(racer, game)| SimResult {
+            name: racer.name.clone(),
+            wpm: game.wpm(),
+            accuracy: game.accuracy(),
+            errors: game.errors,
+            keystrokes: game.keystrokes,
+            elapsed: game.elapsed,
+            finished: game.status == GameStatus::Finished,
+            replay: game.replay.clone(),
+        })
+        .collect();
+    SimOutcome { timeline, results }
+}
+
+fn snapshot(game: &GameState) -> RacerSnapshot {
+    RacerSnapshot {
+        current_char: game.current_char,
+        progress: game.progress(),
+        wpm: game.wpm(),
+        errors: game.errors,
+        finished: game.status == GameStatus::Finished,
+    }
+}
@@ -0,0 +1,226 @@
+// --- Per-key and per-bigram typing statistics ---
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{GameState, MeditationQuote, Replay, BACKSPACE};
+use crate::keyboard::layout::unshifted;
+
+/// Keys with fewer attempts than this are not judged weak or strong
+pub const MIN_SAMPLES: u32 = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyStat {
+    pub hits: u32,
+    pub misses: u32,
+    /// Time spent on correct presses that followed another key
+    pub total_ms: u64,
+    pub timed_hits: u32,
+}
+
+impl KeyStat {
+    pub fn attempts(&self) -> u32 {
+        self.hits + self.misses
+    }
+
+    pub fn error_rate(&self) -> f32 {
+        if self.attempts() == 0 {
+            0.0
+        } else {
+            self.misses as f32 / self.attempts() as f32
+        }
+    }
+
+    pub fn average_ms(&self) -> Option<f32> {
+        (self.timed_hits > 0).then(|| self.total_ms as f32 / self.timed_hits as f32)
+    }
+
+    fn merge(&mut self, other: &KeyStat) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.total_ms += other.total_ms;
+        self.timed_hits += other.timed_hits;
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyStats {
+    pub keys: BTreeMap<char, KeyStat>,
+    pub bigrams: BTreeMap<String, KeyStat>,
+}
+
+impl KeyStats {
+    pub fn merge(&mut self, other: &KeyStats) {
+        for (key, stat) in &other.keys {
+            self.keys.entry(*key).or_default().merge(stat);
+        }
+        for (bigram, stat) in &other.bigrams {
+            self.bigrams.entry(bigram.clone()).or_default().merge(stat);
+        }
+    }
+
+    /// Average time per correct key over everything recorded
+    pub fn overall_average_ms(&self) -> Option<f32> {
+        let (total, count) = self
+            .keys
+            .values()
+            .fold((0u64, 0u32), |(t, c), s| (t + s.total_ms, c + s.timed_hits));
+        (count > 0).then(|| total as f32 / count as f32)
+    }
+
+    /// How much a stat stands out: error rate, plus how much slower than
+    /// average it is. Zero means no worse than typical.
+    pub fn weakness(&self, stat: &KeyStat) -> f32 {
+        let slowness = match (stat.average_ms(), self.overall_average_ms()) {
+            (Some(avg), Some(overall)) if overall > 0.0 => (avg / overall - 1.0).max(0.0),
+            _ => 0.0,
+        };
+        stat.error_rate() * 2.0 + slowness
+    }
+
+    /// Up to `count` keys, weakest first
+    pub fn weakest_keys(&self, count: usize) -> Vec<char> {
+        let mut keys: Vec<(char, f32)> = self
+            .keys
+            .iter()
+            .filter(|(_, s)| s.attempts() >= MIN_SAMPLES)
+            .map(|(k, s)| (*k, self.weakness(s)))
+            .filter(|(_, w)| *w > 0.0)
+            .collect();
+        keys.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        keys.into_iter().take(count).map(|(k, _)| k).collect()
+    }
+
+    /// Up to `count` bigrams, weakest first
+    pub fn weakest_bigrams(&self, count: usize) -> Vec<String> {
+        let mut bigrams: Vec<(&String, f32)> = self
+            .bigrams
+            .iter()
+            .filter(|(_, s)| s.attempts() >= MIN_SAMPLES)
+            .map(|(b, s)| (b, self.weakness(s)))
+            .filter(|(_, w)| *w > 0.0)
+            .collect();
+        bigrams.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+        bigrams.into_iter().take(count).map(|(b, _)| b.clone()).collect()
+    }
+}
+
+/// Replay a run through the typing engine and tally every key press against
+/// the character that was expected at that moment
+pub fn analyze_replay(quotes: &[MeditationQuote], replay: &Replay) -> KeyStats {
+    let mut stats = KeyStats::default();
+    let mut game = GameState::new(quotes.to_vec());
+    game.start_race_at(&replay.config, 0);
+    let mut last_ms: Option<u64> = None;
+    for event in &replay.active_events() {
+        let ms = event.milliseconds_since_epoch_utc;
+        let interval = last_ms.map(|last| ms.saturating_sub(last));
+        last_ms = Some(ms);
+        if event.character == BACKSPACE {
+            game.backspace(ms);
+            continue;
+        }
+        let text: Vec<char> = game.target_text().chars().collect();
+        let position = game.current_char;
+        let Some(&expected) = text.get(position) else {
+            continue;
+        };
+        let correct = game.type_char(event.character, ms);
+        // Tallied by physical key, so 'T' and 't' are both the T key
+        let expected = unshifted(expected);
+        let previous = position.checked_sub(1).and_then(|p| text.get(p)).map(|&p| unshifted(p));
+        let bigram = previous
+            .filter(|p| *p != ' ' && expected != ' ')
+            .map(|p| format!("{}{}", p, expected));
+        let tally = |stat: &mut KeyStat| {
+            if correct {
+                stat.hits += 1;
+                if let Some(interval) = interval {
+                    stat.total_ms += interval;
+                    stat.timed_hits += 1;
+                }
+            } else {
+                stat.misses += 1;
+            }
+        };
+        tally(stats.keys.entry(expected).or_default());
+        if let Some(bigram) = bigram {
+            tally(stats.bigrams.entry(bigram).or_default());
+        }
+    }
+    stats
+}
+
+/// Words taking this many times the race's average per character are slow
+pub const SLOW_WORD_FACTOR: f32 = 1.5;
+
+/// How one word of a run went
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordAttempt {
+    pub word: String,
+    pub mistyped: bool,
+    /// Unusually slow compared with the rest of the run
+    pub slow: bool,
+    /// None for the first word, which has no keystroke before it to time from
+    pub ms_per_char: Option<f32>,
+}
+
+/// Replay a run, noting when each passage position was last typed, with
+/// paused time cut out.
+/// Returns the finished game along with the times.
+pub fn typed_times(quotes: &[MeditationQuote], replay: &Replay) -> (GameState, Vec<Option<u64>>) {
+    let mut game = GameState::new(quotes.to_vec());
+    game.start_race_at(&replay.config, 0);
+    let mut typed_at: Vec<Option<u64>> = Vec::new();
+    for event in &replay.active_events() {
+        let ms = event.milliseconds_since_epoch_utc;
+        let before = game.current_char;
+        game.type_char(event.character, ms);
+        if game.current_char > before {
+            if typed_at.len() <= before {
+                typed_at.resize(before + 1, None);
+            }
+            typed_at[before] = Some(ms);
+        }
+    }
+    (game, typed_at)
+}
+
+/// Replay a run and report every word that was typed to the end
+pub fn word_attempts(quotes: &[MeditationQuote], replay: &Replay) -> Vec<WordAttempt> {
+    let (game, typed_at) = typed_times(quotes, replay);
+    let text: Vec<char> = game.target_text().chars().collect();
+    let typed = game.current_char.min(text.len());
+    let mut attempts = Vec::new();
+    let mut start = 0;
+    while start < typed {
+        let end = text[start..].iter().position(|c| *c == ' ').map(|i| start + i).unwrap_or(text.len());
+        if end > typed {
+            break;
+        }
+        let time = |p: usize| typed_at.get(p).copied().flatten();
+        let ms_per_char = start
+            .checked_sub(1)
+            .and_then(time)
+            .zip(end.checked_sub(1).and_then(time))
+            .filter(|_| end > start)
+            .map(|(from, to)| to.saturating_sub(from) as f32 / (end - start) as f32);
+        if end > start {
+            attempts.push(WordAttempt {
+                word: text[start..end].iter().collect(),
+                mistyped: (start..end).any(|p| game.mistakes.contains(&p)),
+                slow: false,
+                ms_per_char,
+            });
+        }
+        start = end + 1;
+    }
+    let timed: Vec<f32> = attempts.iter().filter_map(|a| a.ms_per_char).collect();
+    if !timed.is_empty() {
+        let average = timed.iter().sum::<f32>() / timed.len() as f32;
+        for attempt in &mut attempts {
+            attempt.slow = attempt.ms_per_char.is_some_and(|ms| ms > average * SLOW_WORD_FACTOR);
+        }
+    }
+    attempts
+}
@@ -0,0 +1,249 @@
+// --- Persistence Backends ---
+//
+// Everything the game saves goes through `Storage` using namespaced keys
+// such as "profiles" or "replays/ada-1747078025974". The browser build keeps
+// them in localStorage, the desktop build in files under the per-user data
+// directory, and tests use `MemoryStorage`.
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<String>;
+    /// Store a value, returning false if the backend refused it
+    fn put(&mut self, key: &str, value: &str) -> bool;
+    /// Remove a key, returning false if it did not exist
+    fn delete(&mut self, key: &str) -> bool;
+    /// Keys directly under a namespace, without the namespace prefix
+    fn list(&self, namespace: &str) -> Vec<String>;
+    /// Size in bytes of the value stored under a key
+    fn size(&self, key: &str) -> Option<usize> {
+        self.get(key).map(|v| v.len())
+    }
+    /// Total bytes stored under a namespace ("" for the top level)
+    fn namespace_size(&self, namespace: &str) -> usize {
+        self.list(namespace)
+            .iter()
+            .filter_map(|name| self.size(&join_key(namespace, name)))
+            .sum()
+    }
+}
+
+/// Build a namespaced key, e.g. `join_key("replays", "ada-1")` is "replays/ada-1"
+pub fn join_key(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", namespace, name)
+    }
+}
+
+/// Keys of `keys` that sit directly in `namespace`, stripped of the prefix
+fn keys_in_namespace<'a>(keys: impl Iterator<Item = &'a str>, namespace: &str) -> Vec<String> {
+    let prefix = if namespace.is_empty() { String::new() } else { format!("{}/", namespace) };
+    keys.filter_map(|k| k.strip_prefix(prefix.as_str()))
+        .filter(|rest| !rest.is_empty() && !rest.contains('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Load and deserialize a value, falling back to its default if missing or unreadable
+pub fn load_json<T: DeserializeOwned + Default>(storage: &dyn Storage, key: &str) -> T {
+    storage
+        .get(key)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_json<T: Serialize + ?Sized>(storage: &mut dyn Storage, key: &str, value: &T) -> bool {
+    match serde_json::to_string(value) {
+        Ok(json) => storage.put(key, &json),
+        Err(_) => false,
+    }
+}
+
+/// Volatile storage, used by tests and as a fallback when nothing else is available
+#[derive(Default, Debug, Clone)]
+pub struct MemoryStorage {
+    pub values: BTreeMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+    fn put(&mut self, key: &str, value: &str) -> bool {
+        self.values.insert(key.to_string(), value.to_string());
+        true
+    }
+    fn delete(&mut self, key: &str) -> bool {
+        self.values.remove(key).is_some()
+    }
+    fn list(&self, namespace: &str) -> Vec<String> {
+        keys_in_namespace(self.values.keys().map(String::as_str), namespace)
+    }
+}
+
+/// Browser localStorage, with every key prefixed so other pages' data is ignored
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStorage {
+    prefix: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    pub fn new() -> Self {
+        Self { prefix: "typer_racer:".to_string() }
+    }
+
+    fn storage(&self) -> Option<web_sys::Storage> {
+        web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Default for LocalStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.storage()?.get_item(&format!("{}{}", self.prefix, key)).ok().flatten()
+    }
+    fn put(&mut self, key: &str, value: &str) -> bool {
+        match self.storage() {
+            Some(storage) => storage.set_item(&format!("{}{}", self.prefix, key), value).is_ok(),
+            None => false,
+        }
+    }
+    fn delete(&mut self, key: &str) -> bool {
+        let existed = self.get(key).is_some();
+        if let Some(storage) = self.storage() {
+            let _ = storage.remove_item(&format!("{}{}", self.prefix, key));
+        }
+        existed
+    }
+    fn list(&self, namespace: &str) -> Vec<String> {
+        let Some(storage) = self.storage() else {
+            return Vec::new();
+        };
+        let len = storage.length().unwrap_or(0);
+        let keys: Vec<String> = (0..len)
+            .filter_map(|i| storage.key(i).ok().flatten())
+            .filter_map(|k| k.strip_prefix(self.prefix.as_str()).map(str::to_string))
+            .collect();
+        keys_in_namespace(keys.iter().map(String::as_str), namespace)
+    }
+}
+
+/// One JSON file per key under a root directory; namespaces become subdirectories
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    pub root: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new(root: impl Into<std::path::PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The file for `key`, or None if a part of it is empty, "." or "..",
+    /// which could reach outside `root`
+    fn path(&self, key: &str) -> Option<std::path::PathBuf> {
+        let (namespace, name) = key.rsplit_once('/').unwrap_or(("", key));
+        if !is_safe_part(name) {
+            return None;
+        }
+        let mut path = self.dir(namespace)?;
+        path.push(format!("{}.json", name));
+        Some(path)
+    }
+
+    /// The directory for `namespace`, refusing unsafe parts as `path` does
+    fn dir(&self, namespace: &str) -> Option<std::path::PathBuf> {
+        let mut dir = self.root.clone();
+        if namespace.is_empty() {
+            return Some(dir);
+        }
+        for part in namespace.split('/') {
+            if !is_safe_part(part) {
+                return None;
+            }
+            dir.push(part);
+        }
+        Some(dir)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn is_safe_part(part: &str) -> bool {
+    !matches!(part, "" | "." | "..") && !part.contains('\\')
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for FileStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.path(key)?).ok()
+    }
+    fn put(&mut self, key: &str, value: &str) -> bool {
+        let Some(path) = self.path(key) else {
+            return false;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        std::fs::write(path, value).is_ok()
+    }
+    fn delete(&mut self, key: &str) -> bool {
+        self.path(key).is_some_and(|path| std::fs::remove_file(path).is_ok())
+    }
+    fn list(&self, namespace: &str) -> Vec<String> {
+        let Some(Ok(entries)) = self.dir(namespace).map(std::fs::read_dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                name.strip_suffix(".json").map(str::to_string)
+            })
+            .collect();
+        names.sort();
+        names
+    }
+    fn size(&self, key: &str) -> Option<usize> {
+        std::fs::metadata(self.path(key)?).ok().map(|m| m.len() as usize)
+    }
+}
+
+/// Per-user directory for saved profiles and replays (native only)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn data_dir() -> std::path::PathBuf {
+    use std::path::PathBuf;
+    let env_dir = |var: &str| std::env::var_os(var).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|h| h.join("Library").join("Application Support"))
+    } else {
+        env_dir("XDG_DATA_HOME").or_else(|| env_dir("HOME").map(|h| h.join(".local").join("share")))
+    };
+    base.unwrap_or_else(|| PathBuf::from(".")).join("typer-racer")
+}
+
+/// The storage backend for the platform we are running on
+#[cfg(target_arch = "wasm32")]
+pub fn default_storage() -> Box<dyn Storage> {
+    Box::new(LocalStorage::new())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_storage() -> Box<dyn Storage> {
+    Box::new(FileStorage::new(data_dir()))
+}
@@ -30,3 +30,1200 @@ fn test_error_counting() {
     }
     assert_eq!(game.errors, 1);
 }
+
+#[test]
+fn test_race_finishes_on_last_char() {
+    let quote = MeditationQuote {
+        original_quotes: vec!["ab".to_string()],
+        expanded_meditation: "a—b".to_string(),
+    };
+    let mut game = GameState::new(vec![quote]);
+    game.start_race_at(&crate::RaceConfig { quote_index: 0, seed: "seed".to_string(), ..Default::default() }, 0);
+    assert!(game.type_char('a', 0));
+    assert!(!game.type_char('x', 10));
+    assert!(game.type_char('-', 20));
+    assert!(game.type_char('b', 30));
+    assert_eq!(game.status, GameStatus::Finished);
+    assert_eq!(game.errors, 1);
+    assert_eq!(game.input_buffer, "a—b");
+    assert_eq!(game.replay.len(), 4);
+}
+
+#[test]
+fn test_profile_history_roundtrip() {
+    use crate::profile::{ProfileStore, RaceRecord};
+    let mut store = ProfileStore::default();
+    assert!(store.create("ada"));
+    assert!(!store.create("ada"));
+    assert!(store.create("grace"));
+    assert!(!store.create("a/b"));
+    assert!(!store.create("..\\x"));
+    assert!(store.select("ada"));
+    assert!(store.record_race(RaceRecord {
+        date: chrono::Utc::now(),
+        quote_index: 3,
+        seed: "42".to_string(),
+        mode: crate::GameMode::Race,
+        wpm: 71.5,
+        accuracy: 97.0,
+        replay_ref: "ada-42".to_string(),
+    }));
+    let json = serde_json::to_string(&store).unwrap();
+    let loaded: ProfileStore = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded, store);
+    assert_eq!(loaded.active().unwrap().best_wpm(), Some(71.5));
+    assert!(loaded.profiles[1].history.is_empty());
+}
+
+#[test]
+fn test_leaderboard_ranking_and_filters() {
+    use crate::leaderboard::{Leaderboard, LeaderboardEntry, LeaderboardFilter};
+    use crate::GameMode;
+    let entry = |player: &str, quote_index: usize, mode: GameMode, wpm: f32, accuracy: f32| LeaderboardEntry {
+        player: player.to_string(),
+        date: chrono::Utc::now(),
+        pack: "expanded-meditations".to_string(),
+        quote_index,
+        mode,
+        policy: Default::default(),
+        seed: "1".to_string(),
+        wpm,
+        accuracy,
+        replay_ref: format!("{}-1", player),
+    };
+    let mut board = Leaderboard::default();
+    let timed = GameMode::Timed { seconds: 30 };
+    board.add(entry("ada", 0, GameMode::Race, 60.0, 90.0));
+    board.add(entry("grace", 0, GameMode::Race, 60.0, 95.0));
+    board.add(entry("linus", 1, GameMode::Race, 80.0, 99.0));
+    board.add(entry("ken", 0, timed, 90.0, 99.0));
+
+    let all: Vec<&str> = board.ranked(&LeaderboardFilter::default()).iter().map(|e| e.player.as_str()).collect();
+    assert_eq!(all, vec!["ken", "linus", "grace", "ada"]);
+
+    let filter = LeaderboardFilter {
+        quote_index: Some(0),
+        mode: Some(GameMode::Race),
+        ..Default::default()
+    };
+    let quote0: Vec<&str> = board.ranked(&filter).iter().map(|e| e.player.as_str()).collect();
+    assert_eq!(quote0, vec!["grace", "ada"]);
+    assert_eq!(board.modes(), vec![GameMode::Race, timed]);
+
+    // Only the best runs on each quote and mode are kept
+    for i in 0..crate::leaderboard::MAX_ENTRIES {
+        board.add(entry("fast", 0, GameMode::Race, 100.0 + i as f32, 99.0));
+    }
+    let quote0 = board.ranked(&filter);
+    assert_eq!(quote0.len(), crate::leaderboard::MAX_ENTRIES);
+    assert!(quote0.iter().all(|e| e.player == "fast"));
+    board.add(entry("slow", 0, GameMode::Race, 1.0, 50.0));
+    assert_eq!(board.ranked(&filter).len(), crate::leaderboard::MAX_ENTRIES);
+    assert!(board.ranked(&filter).iter().all(|e| e.player == "fast"));
+    assert_eq!(board.entries.len(), crate::leaderboard::MAX_ENTRIES + 2);
+}
+
+#[test]
+fn test_simulator_replays_relative_to_start() {
+    use crate::keyboard::simulator::KeyboardSimulator;
+    use crate::time::ManualClock;
+    use crate::ReplayEvent;
+    let events = vec![
+        ReplayEvent { milliseconds_since_epoch_utc: 5_000, quote_index: 0, character: 'a' },
+        ReplayEvent { milliseconds_since_epoch_utc: 5_200, quote_index: 0, character: 'b' },
+    ];
+    let clock = ManualClock::new(100);
+    let mut sim = KeyboardSimulator::new();
+    sim.start_replay(events, &clock);
+    assert!(sim.tick(&clock).is_some());
+    clock.advance(150);
+    assert!(sim.tick(&clock).is_none());
+    clock.advance(50);
+    assert!(sim.tick(&clock).is_some());
+    clock.advance(100);
+    assert!(sim.tick(&clock).is_none());
+    assert!(!sim.is_running());
+}
+
+#[test]
+fn test_memory_storage_namespaces() {
+    use crate::keyboard::replay::{delete_replay, list_replays, load_replay_named, save_replay_named};
+    use crate::profile::{load_profiles, save_profiles, ProfileStore};
+    use crate::storage::{MemoryStorage, Storage};
+    use crate::{Replay, ReplayEvent};
+    let mut storage = MemoryStorage::default();
+    let replay = Replay {
+        events: vec![ReplayEvent { milliseconds_since_epoch_utc: 1, quote_index: 0, character: 'x' }],
+        ..Replay::default()
+    };
+    assert!(save_replay_named(&mut storage, "ada-1", &replay));
+    assert!(save_replay_named(&mut storage, "ada-2", &replay));
+    assert!(save_replay_named(&mut storage, &crate::keyboard::replay::ghost_name("2"), &replay));
+    let mut store = ProfileStore::default();
+    store.create("ada");
+    assert!(save_profiles(&mut storage, &store));
+
+    assert_eq!(list_replays(&storage), vec!["ada-1".to_string(), "ada-2".to_string()]);
+    assert_eq!(storage.list(""), vec!["profiles".to_string()]);
+    assert_eq!(load_replay_named(&storage, "ada-2").events[0].character, 'x');
+    assert_eq!(load_profiles(&storage), store);
+    assert_eq!(storage.namespace_size("replays"), 3 * storage.size("replays/ada-1").unwrap());
+    assert_eq!(load_replay_named(&storage, "ghost-2").events.len(), 1);
+
+    assert!(delete_replay(&mut storage, "ada-1"));
+    assert!(!delete_replay(&mut storage, "ada-1"));
+    assert!(load_replay_named(&storage, "ada-1").events.is_empty());
+}
+
+#[test]
+fn test_file_storage_roundtrip() {
+    use crate::storage::{FileStorage, Storage};
+    let root = std::env::temp_dir().join(format!("typer-racer-test-{}", std::process::id()));
+    let mut storage = FileStorage::new(&root);
+    assert!(storage.put("replays/ada.v2", "[]"));
+    assert!(storage.put("profiles", "{}"));
+    assert_eq!(storage.get("replays/ada.v2").as_deref(), Some("[]"));
+    assert_eq!(storage.list("replays"), vec!["ada.v2".to_string()]);
+    assert_eq!(storage.size("profiles"), Some(2));
+    assert!(storage.delete("profiles"));
+    assert_eq!(storage.get("profiles"), None);
+    // Keys can't climb out of the data directory
+    assert!(!storage.put("replays/../../escaped", "{}"));
+    assert!(!storage.put("replays//x", "{}"));
+    assert!(!storage.put("..", "{}"));
+    assert!(!root.parent().unwrap().join("escaped.json").exists());
+    assert!(storage.list("replays/..").is_empty());
+    let _ = std::fs::remove_dir_all(root);
+}
+
+fn sample_quotes() -> Vec<MeditationQuote> {
+    vec![
+        MeditationQuote {
+            original_quotes: vec![],
+            expanded_meditation: "steady hands type calm words".to_string(),
+        },
+        MeditationQuote {
+            original_quotes: vec![],
+            expanded_meditation: "focus on the next key".to_string(),
+        },
+    ]
+}
+
+#[test]
+fn test_word_count_mode_is_deterministic() {
+    use crate::{GameMode, RaceConfig};
+    let config = RaceConfig {
+        quote_index: 0,
+        seed: "abc".to_string(),
+        mode: GameMode::WordCount { words: 10 },
+        ..Default::default()
+    };
+    let mut a = GameState::new(sample_quotes());
+    let mut b = GameState::new(sample_quotes());
+    a.start_race_at(&config, 0);
+    b.start_race_at(&config, 0);
+    assert_eq!(a.target_text(), b.target_text());
+    assert_eq!(a.target_text().split(' ').count(), 10);
+}
+
+#[test]
+fn test_timed_mode_streams_words_until_time_is_up() {
+    use crate::{GameMode, RaceConfig};
+    let mut game = GameState::new(sample_quotes());
+    game.start_race_at(&RaceConfig {
+        quote_index: 0,
+        seed: "abc".to_string(),
+        mode: GameMode::Timed { seconds: 15 },
+        ..Default::default()
+    }, 0);
+    let initial_len = game.target_text().chars().count();
+    let mut typed = 0;
+    while typed < initial_len {
+        let c = game.target_text().chars().nth(game.current_char).unwrap();
+        assert!(game.type_char(c, typed as u64));
+        typed += 1;
+    }
+    assert_eq!(game.status, GameStatus::Running);
+    assert!(game.target_text().chars().count() > initial_len);
+    game.set_elapsed(7.5);
+    assert!((game.progress() - 0.5).abs() < 1e-6);
+    game.set_elapsed(16.0);
+    assert_eq!(game.status, GameStatus::Finished);
+    assert_eq!(game.elapsed, 15.0);
+}
+
+#[test]
+fn test_bare_array_replays_still_load() {
+    use crate::keyboard::replay::parse_replay;
+    let old = r#"[{"milliseconds_since_epoch_utc":1,"quote_index":0,"character":"T"}]"#;
+    let replay = parse_replay(old).unwrap();
+    assert_eq!(replay.events.len(), 1);
+    assert_eq!(replay.config.mode, crate::GameMode::Race);
+    let new = serde_json::to_string(&replay).unwrap();
+    assert_eq!(parse_replay(&new).unwrap().events[0].character, 'T');
+}
+
+fn start_with_policy(text: &str, policy: crate::ErrorPolicy) -> GameState {
+    let quote = MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: text.to_string(),
+    };
+    let mut game = GameState::new(vec![quote]);
+    game.start_race_at(&crate::RaceConfig { policy, ..Default::default() }, 0);
+    game
+}
+
+#[test]
+fn test_stop_on_error_waits_for_the_right_key() {
+    let mut game = start_with_policy("ab", crate::ErrorPolicy::StopOnError);
+    assert!(!game.type_char('x', 0));
+    assert_eq!(game.current_char, 0);
+    assert!(!game.backspace(1));
+    assert!(game.type_char('a', 2));
+    assert!(game.type_char('b', 3));
+    assert_eq!(game.status, GameStatus::Finished);
+    assert!((game.accuracy() - 200.0 / 3.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_forgiving_advances_and_backspace_fixes() {
+    let mut game = start_with_policy("ab", crate::ErrorPolicy::Forgiving);
+    assert!(!game.type_char('x', 0));
+    assert_eq!(game.input_buffer, "x");
+    assert!(game.backspace(1));
+    assert!(game.type_char('a', 2));
+    assert!(game.type_char('b', 3));
+    assert_eq!(game.status, GameStatus::Finished);
+    assert_eq!(game.correct_chars(), 2);
+    assert!(game.mistakes.contains(&0));
+    assert_eq!(game.replay[1].character, crate::game::BACKSPACE);
+}
+
+#[test]
+fn test_word_strict_blocks_space_after_bad_word() {
+    let mut game = start_with_policy("ab cd", crate::ErrorPolicy::WordStrict);
+    game.type_char('a', 0);
+    game.type_char('x', 1);
+    assert!(!game.type_char(' ', 2));
+    assert_eq!(game.current_char, 2);
+    assert!(game.backspace(3));
+    game.type_char('b', 4);
+    assert!(game.type_char(' ', 5));
+    game.type_char('c', 6);
+    game.type_char('x', 7);
+    assert_eq!(game.status, GameStatus::Running);
+    game.backspace(8);
+    game.type_char('d', 9);
+    assert_eq!(game.status, GameStatus::Finished);
+}
+
+#[test]
+fn test_no_backspace_keeps_mistakes() {
+    let mut game = start_with_policy("ab", crate::ErrorPolicy::NoBackspace);
+    game.type_char('x', 0);
+    assert!(!game.backspace(1));
+    game.type_char('b', 2);
+    assert_eq!(game.status, GameStatus::Finished);
+    assert_eq!(game.correct_chars(), 1);
+}
+
+#[test]
+fn test_typing_area_char_states() {
+    use crate::keyboard::display::{cell_index, char_states, current_word_range, CharState};
+    use crate::policy::ErrorPolicy;
+    use std::collections::{BTreeMap, BTreeSet};
+    let mistakes: BTreeSet<usize> = [1].into_iter().collect();
+    let states: Vec<CharState> =
+        char_states("ab cd", "ab", &mistakes, &BTreeMap::new()).iter().map(|c| c.state).collect();
+    assert_eq!(states[..3], [CharState::Correct, CharState::Corrected, CharState::Pending]);
+    assert_eq!(char_states("ab", "x", &BTreeSet::new(), &BTreeMap::new())[0].shown, 'a');
+
+    // Typing on past a word piles extras up in front of its space, and the
+    // next word still lines up once a space moves on
+    let quotes = vec![MeditationQuote { original_quotes: vec![], expanded_meditation: "cat dog".to_string() }];
+    let mut game = GameState::new(quotes);
+    game.start_race_at(&crate::RaceConfig { policy: ErrorPolicy::Forgiving, ..Default::default() }, 0);
+    for c in "catxyz".chars() {
+        game.type_char(c, 0);
+    }
+    assert_eq!(game.extras.get(&3).map(String::as_str), Some("xyz"));
+    assert_eq!((game.current_char, game.errors), (3, 3));
+    game.backspace(0);
+    let cells = char_states(game.target_text(), &game.input_buffer, &game.mistakes, &game.extras);
+    let shown: String = cells.iter().map(|c| c.shown).collect();
+    assert_eq!(shown, "catxy dog");
+    let states: Vec<CharState> = cells.iter().map(|c| c.state).collect();
+    assert_eq!(states[2..6], [CharState::Correct, CharState::Extra, CharState::Extra, CharState::Pending]);
+    assert_eq!((cell_index(&cells, 3), cell_index(&cells, 4)), (5, 6));
+    for c in " dog".chars() {
+        game.type_char(c, 0);
+    }
+    assert_eq!(game.status, GameStatus::Finished);
+    assert_eq!(game.input_buffer, "cat dog");
+    let cells = char_states(game.target_text(), &game.input_buffer, &game.mistakes, &game.extras);
+    assert!(cells[6..].iter().all(|c| c.state == CharState::Correct));
+    assert_eq!(current_word_range("ab cd", 1), 0..2);
+    assert_eq!(current_word_range("ab cd", 2), 0..2);
+    assert_eq!(current_word_range("ab cd", 3), 3..5);
+    assert_eq!(current_word_range("ab cd", 5), 3..5);
+    assert_eq!(crate::keyboard::display::typing_area_label("ab cd ef", 4), "word 2 of 3: cd");
+}
+
+#[test]
+fn test_analyze_replay_finds_weak_keys() {
+    use crate::stats::analyze_replay;
+    use crate::GameMode;
+    let quotes = vec![crate::game::MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "aqa aQa Aqa".to_string(),
+    }];
+    let mut game = GameState::new(quotes.clone());
+    game.start_race_at(&crate::RaceConfig { mode: GameMode::Race, ..Default::default() }, 0);
+    let mut now = 0;
+    for expected in "aqa aQa Aqa".chars() {
+        // Every q is missed once, then found slowly
+        if expected.eq_ignore_ascii_case(&'q') {
+            game.type_char('w', now);
+            now += 400;
+        }
+        now += 100;
+        game.type_char(expected, now);
+    }
+    let replay = game.recorded_replay();
+    let stats = analyze_replay(&quotes, &replay);
+    assert_eq!(stats.keys[&'q'].misses, 3);
+    assert_eq!(stats.keys[&'q'].hits, 3);
+    assert_eq!(stats.keys[&'a'].misses, 0);
+    // Keys are counted whether shifted or not, so lowercase drill words still hit them
+    assert!(!stats.keys.contains_key(&'Q'));
+    assert_eq!(stats.weakest_keys(1), vec!['q']);
+    assert!(stats.weakest_bigrams(2).contains(&"aq".to_string()));
+    assert!(crate::drill::DrillSpec::from_stats(&stats, 1).score("quiet") > 0);
+}
+
+#[test]
+fn test_drill_is_deterministic_and_targets_weak_keys() {
+    use crate::drill::{next_level, DrillSpec};
+    let quotes = vec![crate::game::MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "quiet quick sun tree rock leaf wind".to_string(),
+    }];
+    let spec = DrillSpec { keys: vec!['q'], bigrams: vec!["qu".to_string()], level: 1 };
+    let drill = spec.generate(&quotes, "seed");
+    assert_eq!(drill, spec.generate(&quotes, "seed"));
+    let words: Vec<&str> = drill.split(' ').collect();
+    assert_eq!(words.len(), spec.word_count());
+    assert!(words.iter().all(|w| w.chars().count() <= spec.max_word_len()));
+    let targeted = words.iter().filter(|w| w.contains('q')).count();
+    assert!(targeted * 2 > words.len(), "drill: {}", drill);
+
+    let config = crate::RaceConfig {
+        seed: "seed".to_string(),
+        mode: crate::GameMode::Drill,
+        drill: Some(spec),
+        ..Default::default()
+    };
+    let mut game = GameState::new(quotes);
+    game.start_race_at(&config, 0);
+    assert_eq!(game.target_text(), drill);
+    assert_eq!(game.race_config(), config);
+
+    assert_eq!(next_level(1, 99.0), 2);
+    assert_eq!(next_level(5, 99.0), 5);
+    assert_eq!(next_level(3, 80.0), 2);
+    assert_eq!(next_level(1, 80.0), 1);
+}
+
+#[test]
+fn test_layout_finger_map() {
+    use crate::keyboard::layout::{needs_shift, Finger, KeyboardLayout};
+    let qwerty = KeyboardLayout::Qwerty;
+    assert_eq!(qwerty.finger_for('f'), Some(Finger::LeftIndex));
+    assert_eq!(qwerty.finger_for('J'), Some(Finger::RightIndex));
+    assert_eq!(qwerty.finger_for('?'), Some(Finger::RightPinky));
+    assert_eq!(qwerty.finger_for(' '), Some(Finger::Thumb));
+    assert_eq!(KeyboardLayout::Dvorak.finger_for('u'), Some(Finger::LeftIndex));
+    assert!(needs_shift('A') && needs_shift('!') && !needs_shift('a'));
+}
+
+#[test]
+fn test_lessons_use_stage_keys_and_unlock_in_order() {
+    use crate::curriculum::{LessonProgress, LessonStage};
+    use crate::keyboard::layout::KeyboardLayout;
+    let quotes = vec![crate::game::MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "a sad lad had a flask; dad asks all. Glass halls fall".to_string(),
+    }];
+    let home = LessonStage::HomeRow.letters(KeyboardLayout::Qwerty);
+    let text = LessonStage::HomeRow.lesson_text(&quotes, KeyboardLayout::Qwerty, "seed");
+    assert_eq!(text, LessonStage::HomeRow.lesson_text(&quotes, KeyboardLayout::Qwerty, "seed"));
+    assert!(text.chars().all(|c| c == ' ' || home.contains(&c)), "{}", text);
+    let capitals = LessonStage::Capitals.lesson_text(&quotes, KeyboardLayout::Qwerty, "seed");
+    assert!(capitals.chars().any(|c| !c.is_lowercase() && c != ' '), "{}", capitals);
+
+    let mut progress = LessonProgress::default();
+    assert!(progress.is_unlocked(LessonStage::HomeRow));
+    assert!(!progress.is_unlocked(LessonStage::TopRow));
+    assert!(!progress.record(LessonStage::HomeRow, 10.0, 99.0));
+    assert!(progress.record(LessonStage::HomeRow, 30.0, 95.0));
+    assert!(!progress.record(LessonStage::HomeRow, 40.0, 99.0));
+    assert!(progress.is_unlocked(LessonStage::TopRow));
+    assert!(!progress.is_unlocked(LessonStage::BottomRow));
+
+    let json = serde_json::to_string(&progress).unwrap();
+    assert_eq!(serde_json::from_str::<LessonProgress>(&json).unwrap(), progress);
+}
+
+#[test]
+fn test_word_attempts_flag_mistyped_and_slow_words() {
+    use crate::stats::word_attempts;
+    let quotes = vec![crate::game::MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "one two three four".to_string(),
+    }];
+    let mut game = GameState::new(quotes.clone());
+    game.start_race_at(&crate::RaceConfig::default(), 0);
+    let mut now = 0;
+    for (i, c) in "one two three four".chars().enumerate() {
+        // "two" is mistyped, "four" is typed at a crawl
+        if i == 4 {
+            game.type_char('x', now);
+        }
+        now += if i >= 14 { 1000 } else { 100 };
+        game.type_char(c, now);
+    }
+    let replay = game.recorded_replay();
+    let attempts = word_attempts(&quotes, &replay);
+    let words: Vec<&str> = attempts.iter().map(|a| a.word.as_str()).collect();
+    assert_eq!(words, vec!["one", "two", "three", "four"]);
+    assert!(attempts[0].ms_per_char.is_none());
+    assert!(attempts[1].mistyped && !attempts[1].slow);
+    assert!(!attempts[2].mistyped && !attempts[2].slow);
+    assert!(attempts[3].slow && !attempts[3].mistyped);
+}
+
+#[test]
+fn test_review_schedule_spaces_out_clean_runs() {
+    use crate::review::{warm_up_text, ReviewSchedule};
+    use chrono::{Duration, TimeZone, Utc};
+    let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    let mut schedule = ReviewSchedule::default();
+    schedule.log_success("calm", now);
+    assert!(schedule.cards.is_empty());
+    schedule.log_failure("stoic", now);
+    schedule.log_failure("virtue", now + Duration::minutes(1));
+    assert_eq!(schedule.due(now + Duration::minutes(2), 8), vec!["stoic", "virtue"]);
+    schedule.log_success("stoic", now);
+    assert_eq!(schedule.due(now + Duration::hours(2), 8), vec!["virtue"]);
+    assert_eq!(schedule.due(now + Duration::days(1), 8), vec!["virtue", "stoic"]);
+    schedule.log_success("stoic", now + Duration::days(1));
+    assert_eq!(schedule.cards["stoic"].interval_days, 6.0);
+    schedule.log_failure("stoic", now + Duration::days(7));
+    assert_eq!(schedule.cards["stoic"].repetitions, 0);
+    assert_eq!(schedule.cards["stoic"].lapses, 2);
+
+    let mut game = GameState::new(vec![]);
+    game.start_race_at(&crate::RaceConfig {
+        mode: crate::GameMode::WarmUp,
+        warm_up: schedule.due(now + Duration::days(7), 8),
+        ..Default::default()
+    }, 0);
+    assert_eq!(game.target_text(), warm_up_text(&game.warm_up));
+    assert_eq!(game.target_text(), "virtue virtue stoic stoic");
+
+    // Finishing a warm-up reschedules its words but stays out of history and key stats
+    let mut store = crate::profile::ProfileStore::default();
+    store.create("ada");
+    game.type_char('x', 0);
+    let text = game.target_text().to_string();
+    for (i, c) in text.chars().enumerate() {
+        game.type_char(c, 100 * i as u64);
+    }
+    assert_eq!(game.status, GameStatus::Finished);
+    store.record_finished(&game, &game.recorded_replay(), "ada-warm");
+    let profile = store.active().unwrap();
+    assert!(profile.history.is_empty());
+    assert!(profile.key_stats.keys.is_empty());
+    assert!(profile.reviews.cards.contains_key("virtue"));
+}
+
+#[test]
+fn test_split_keyboard_routes_keys_by_hand() {
+    use crate::keyboard::layout::KeyboardLayout;
+    use crate::policy::ErrorPolicy;
+    use crate::versus::{route_split_key, split_keyboard_configs};
+    let quotes = sample_quotes();
+    let configs = split_keyboard_configs("duel", KeyboardLayout::Qwerty, ErrorPolicy::Forgiving);
+    assert_eq!((configs.0.policy, configs.1.policy), (ErrorPolicy::Forgiving, ErrorPolicy::Forgiving));
+    let start = |config: &crate::RaceConfig| {
+        let mut game = GameState::new(quotes.clone());
+        game.start_race_at(config, 0);
+        game
+    };
+    let (mut left, right) = (start(&configs.0), start(&configs.1));
+    let left_letters = KeyboardLayout::Qwerty.hand_letters(true);
+    let right_letters = KeyboardLayout::Qwerty.hand_letters(false);
+    assert!(left.target_text().chars().all(|c| c == ' ' || left_letters.contains(&c)));
+    assert!(right.target_text().chars().all(|c| c == ' ' || right_letters.contains(&c)));
+    assert_eq!(route_split_key(KeyboardLayout::Qwerty, 'f', &left, &right), (true, false));
+    assert_eq!(route_split_key(KeyboardLayout::Qwerty, 'j', &left, &right), (false, true));
+    assert_eq!(route_split_key(KeyboardLayout::Qwerty, ' ', &left, &right), (false, false));
+    let first_word = left.target_text().split(' ').next().unwrap().to_string();
+    for c in first_word.chars() {
+        left.type_char(c, 0);
+    }
+    assert_eq!(route_split_key(KeyboardLayout::Qwerty, ' ', &left, &right), (true, false));
+
+    // Each player has their own backspace: Tab on the left, Backspace on the right
+    use crate::keyboard::dispatch::TypingLayer;
+    use crate::keyboard::event::{KeyboardEvent, KeyboardEventHandler, Modifiers};
+    let mut right = right;
+    right.type_char('j', 0);
+    let press = |key: &str| KeyboardEvent::KeyPress {
+        key: key.to_string(),
+        modifiers: Modifiers::default(),
+        repeat: false,
+    };
+    let mut typing = TypingLayer { game: &mut left, opponent: &mut right, split: true, now_ms: 0 };
+    assert!(typing.handle_keyboard_event(&press("BACKSPACE")));
+    assert_eq!((typing.game.current_char, typing.opponent.current_char), (first_word.len(), 0));
+    assert!(typing.handle_keyboard_event(&press(crate::versus::LEFT_BACKSPACE_KEY)));
+    assert_eq!(typing.game.current_char, first_word.len() - 1);
+}
+
+#[test]
+fn test_head_to_head_winner() {
+    use crate::versus::{HeadToHead, PlayerResult};
+    let player = |name: &str, elapsed, wpm, finished| PlayerResult {
+        name: name.to_string(),
+        elapsed,
+        wpm,
+        finished,
+        ..Default::default()
+    };
+    let race = |a, b| HeadToHead { players: [a, b] }.winner();
+    assert_eq!(race(player("a", 30.0, 50.0, true), player("b", 25.0, 60.0, true)), Some(1));
+    assert_eq!(race(player("a", 30.0, 50.0, true), player("b", 20.0, 70.0, false)), Some(0));
+    assert_eq!(race(player("a", 30.0, 50.0, false), player("b", 30.0, 40.0, false)), Some(0));
+    assert_eq!(race(player("a", 30.0, 50.0, true), player("b", 30.0, 50.0, true)), None);
+}
+
+#[test]
+fn test_race_server_lobby_to_race_over() {
+    use crate::net::{ClientMessage, OnlineSession, Recipient, RaceServer, ServerMessage, SessionEvent};
+    let mut server = RaceServer::new(3, 2);
+    let a = server.connect().unwrap();
+    let b = server.connect().unwrap();
+    assert!(server.connect().is_none());
+    server.handle(a, ClientMessage::Join { name: "Ann".to_string() }, 0);
+    server.handle(b, ClientMessage::Join { name: "Bo".to_string() }, 0);
+    let is_start = |(_, m): &(Recipient, ServerMessage)| matches!(m, ServerMessage::RaceStart { .. });
+    assert!(!server.handle(a, ClientMessage::Ready { ready: true }, 0).iter().any(is_start));
+    let outbox = server.handle(b, ClientMessage::Ready { ready: true }, 42);
+    assert!(outbox.iter().any(is_start));
+
+    // A client that saw the lobby counts down into the same race
+    let mut session = OnlineSession::default();
+    session.apply(ServerMessage::Welcome { id: a });
+    for (recipient, message) in &outbox {
+        if *recipient == Recipient::All {
+            if let Some(SessionEvent::Countdown { config, .. }) = session.apply(message.clone()) {
+                assert!(config.quote_index < 3);
+            }
+        }
+    }
+    assert!(session.racing);
+    assert_eq!(session.racers.len(), 1);
+
+    // A seed that could name a path is dropped instead of counted down to
+    let mut hostile = OnlineSession::default();
+    hostile.apply(ServerMessage::Welcome { id: a });
+    for (_, message) in outbox.iter().filter(|(_, m)| !is_start(&(Recipient::All, m.clone()))) {
+        hostile.apply(message.clone());
+    }
+    let config = crate::RaceConfig { seed: "x/../../../home/u/.config/foo".to_string(), ..Default::default() };
+    assert_eq!(hostile.apply(ServerMessage::RaceStart { config, countdown_ms: 3000 }), None);
+    assert!(!hostile.racing);
+    let config = crate::RaceConfig { seed: "online-42".to_string(), ..Default::default() };
+    assert!(hostile.apply(ServerMessage::RaceStart { config, countdown_ms: 3000 }).is_some());
+
+    let finished = server.handle(b, ClientMessage::Finish { wpm: 60.0, accuracy: 98.0, elapsed: 20.0 }, 0);
+    assert!(finished.contains(&(Recipient::All, ServerMessage::Finished { id: b, place: 1 })));
+    let over = server.disconnect(a);
+    let standings = over.iter().find_map(|(_, m)| match m {
+        ServerMessage::RaceOver { standings } => Some(standings.clone()),
+        _ => None,
+    });
+    let standings = standings.unwrap();
+    assert_eq!(standings.iter().map(|s| (s.id, s.finished)).collect::<Vec<_>>(), vec![(b, true), (a, false)]);
+    assert_eq!(server.phase(), crate::net::RacePhase::Lobby);
+}
+
+#[test]
+fn test_net_client_joins_over_localhost() {
+    use crate::net::{ClientMessage, NetClient, ServerMessage};
+    use std::net::TcpListener;
+    use std::time::{Duration, Instant};
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    // Serve just the join handshake over a real socket
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+        let mut server = crate::net::RaceServer::new(1, 2);
+        let id = server.connect().unwrap();
+        if let Ok(tungstenite::Message::Text(text)) = socket.read() {
+            let message: ClientMessage = serde_json::from_str(&text).unwrap();
+            for (_, reply) in server.handle(id, message, 0) {
+                socket.send(tungstenite::Message::text(crate::net::encode(&reply))).unwrap();
+            }
+        }
+        let _ = socket.read();
+    });
+    let client = NetClient::connect(&format!("ws://{}", addr)).unwrap();
+    assert!(client.send(&ClientMessage::Join { name: "Ann".to_string() }));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut received = Vec::new();
+    while received.len() < 2 && Instant::now() < deadline {
+        received.extend(client.poll());
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(matches!(received.first(), Some(ServerMessage::Welcome { .. })));
+    assert!(matches!(received.get(1), Some(ServerMessage::Lobby { players }) if players.len() == 1));
+}
+
+#[test]
+fn test_verify_replay_flags_forged_timing() {
+    use crate::verify::{verify_replay, ExpectedRace, Suspicion};
+    let quotes = vec![MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "the quiet mind sees clearly and acts well".to_string(),
+    }];
+    let record = |gap: &dyn Fn(usize) -> u64| {
+        let mut game = GameState::new(quotes.clone());
+        game.start_race_at(&crate::RaceConfig::default(), 0);
+        let mut ms = 1_000;
+        let text: Vec<char> = game.target_text().chars().collect();
+        for (i, c) in text.into_iter().enumerate() {
+            ms += gap(i);
+            game.type_char(c, ms);
+        }
+        game.recorded_replay()
+    };
+
+    let expected = ExpectedRace::default();
+    // Uneven gaps around 150 ms are an ordinary 80 WPM
+    let human = record(&|i| 120 + (i as u64 * 37) % 70);
+    let verdict = verify_replay(&quotes, &human, &expected, Some(75.0));
+    assert!(verdict.is_plausible(), "{:?}", verdict.suspicions);
+    assert_eq!(verdict.accuracy, 100.0);
+    assert!(verify_replay(&quotes, &human, &expected, Some(150.0))
+        .suspicions
+        .iter()
+        .any(|s| matches!(s, Suspicion::ClaimMismatch { .. })));
+
+    let scripted = record(&|_| 100);
+    assert!(verify_replay(&quotes, &scripted, &expected, None)
+        .suspicions
+        .iter()
+        .any(|s| matches!(s, Suspicion::UniformRhythm { .. })));
+
+    let pasted = record(&|i| if i < 5 { 150 + i as u64 * 20 } else { 1 });
+    let suspicions = verify_replay(&quotes, &pasted, &expected, None).suspicions;
+    assert!(suspicions.iter().any(|s| matches!(s, Suspicion::PasteBurst { .. })));
+    assert!(suspicions.iter().any(|s| matches!(s, Suspicion::SubHumanIntervals { .. })));
+
+    let mut partial = human.clone();
+    partial.events.truncate(10);
+    assert!(verify_replay(&quotes, &partial, &expected, None).suspicions.contains(&Suspicion::Unfinished));
+
+    // A clean run of an easier race than the one it was submitted for
+    let mut swapped = human.clone();
+    swapped.config.mode = crate::GameMode::WordCount { words: 1 };
+    let verdict = verify_replay(&quotes, &swapped, &expected, None);
+    assert!(verdict.suspicions.contains(&Suspicion::WrongPassage));
+    let other_seed = ExpectedRace { seed: "another".to_string(), ..ExpectedRace::default() };
+    assert!(verify_replay(&quotes, &human, &other_seed, None).suspicions.contains(&Suspicion::WrongPassage));
+}
+
+#[test]
+fn test_diff_replays_lines_up_two_runs() {
+    use crate::inspect::{diff_replays, summarize_replay};
+    use crate::keyboard::replay::{is_bare_replay, parse_replay};
+    let quotes = vec![MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "be still".to_string(),
+    }];
+    let run = |gap: u64, typo: bool| {
+        let mut game = GameState::new(quotes.clone());
+        game.start_race_at(&crate::RaceConfig::default(), 0);
+        let mut ms = 0;
+        if typo {
+            game.type_char('x', ms);
+        }
+        for c in "be still".chars() {
+            ms += gap;
+            game.type_char(c, ms);
+        }
+        game.recorded_replay()
+    };
+    let (a, b) = (run(100, false), run(150, true));
+    let diff = diff_replays(&quotes, &a, &b).unwrap();
+    assert_eq!(diff.len(), 8);
+    assert_eq!(diff[1].delta_ms(), Some(300 - 100));
+    assert!(!diff[0].a_missed && diff[0].b_missed);
+    assert_eq!(summarize_replay(&quotes, &b).errors, 1);
+
+    let mut other = a.clone();
+    other.config.quote_index = 1;
+    assert!(diff_replays(&quotes, &a, &other).is_none());
+
+    let old = r#"[{"milliseconds_since_epoch_utc":1,"quote_index":3,"character":"T"}]"#;
+    assert!(is_bare_replay(old));
+    assert_eq!(parse_replay(old).unwrap().config.quote_index, 3);
+    assert!(!is_bare_replay(&serde_json::to_string(&a).unwrap()));
+}
+
+#[test]
+fn test_simulated_replay_is_deterministic() {
+    use crate::keyboard::replay::parse_replay;
+    use crate::sim::{simulate_race, SimRacer, Typist, DEFAULT_STEP_MS};
+    let quotes = vec![MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "act well".to_string(),
+    }];
+    // A recorded run with one typo, as saved to replay.json
+    let recorded = r#"{"config":{"quote_index":0,"seed":"fixture"},"events":[
+        {"milliseconds_since_epoch_utc":5000,"quote_index":0,"character":"a"},
+        {"milliseconds_since_epoch_utc":5180,"quote_index":0,"character":"c"},
+        {"milliseconds_since_epoch_utc":5350,"quote_index":0,"character":"r"},
+        {"milliseconds_since_epoch_utc":5600,"quote_index":0,"character":"t"},
+        {"milliseconds_since_epoch_utc":5790,"quote_index":0,"character":" "},
+        {"milliseconds_since_epoch_utc":6000,"quote_index":0,"character":"w"},
+        {"milliseconds_since_epoch_utc":6140,"quote_index":0,"character":"e"},
+        {"milliseconds_since_epoch_utc":6300,"quote_index":0,"character":"l"},
+        {"milliseconds_since_epoch_utc":6420,"quote_index":0,"character":"l"}]}"#;
+    let replay = parse_replay(recorded).unwrap();
+    let racers = vec![
+        SimRacer { name: "recorded".to_string(), typist: Typist::Replay(replay.events.clone()) },
+        SimRacer {
+            name: "bot".to_string(),
+            typist: Typist::Bot { wpm: 40.0, accuracy: 100.0, seed: "bot".to_string() },
+        },
+    ];
+    let outcome = simulate_race(&quotes, &replay.config, &racers, DEFAULT_STEP_MS);
+    assert_eq!(outcome, simulate_race(&quotes, &replay.config, &racers, DEFAULT_STEP_MS));
+
+    let recorded = &outcome.results[0];
+    assert!(recorded.finished);
+    assert_eq!(recorded.elapsed, 1.42);
+    assert_eq!((recorded.errors, recorded.keystrokes), (1, 9));
+    assert!((recorded.wpm - 8.0 / 5.0 / (1.42 / 60.0)).abs() < 0.01);
+    let bot = &outcome.results[1];
+    assert!(bot.finished && bot.accuracy == 100.0);
+    assert!(bot.elapsed > recorded.elapsed);
+
+    let last = outcome.timeline.last().unwrap();
+    assert!(last.racers.iter().all(|r| r.finished && r.progress == 1.0));
+    let positions: Vec<f32> = outcome.timeline.iter().map(|f| f.racers[1].progress).collect();
+    assert!(positions.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn test_clocks_drive_races_and_ghosts() {
+    use crate::keyboard::simulator::KeyboardSimulator;
+    use crate::time::{Clock, ManualClock, ScaledClock};
+    let clock = ManualClock::new(1_000);
+    let shared = clock.clone();
+    let mut game = GameState::new(vec![MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "ok".to_string(),
+    }]);
+    game.start_race_at(&crate::RaceConfig::default(), clock.now_ms());
+    shared.advance(2_500);
+    game.tick(clock.now_ms());
+    assert_eq!(game.elapsed, 2.5);
+
+    // At half speed a ghost takes twice as long to replay the same keys
+    let slow = ScaledClock::new(clock.clone(), 0.5);
+    let events = vec![
+        crate::ReplayEvent { milliseconds_since_epoch_utc: 0, quote_index: 0, character: 'o' },
+        crate::ReplayEvent { milliseconds_since_epoch_utc: 400, quote_index: 0, character: 'k' },
+    ];
+    let mut ghost = KeyboardSimulator::new();
+    ghost.start_replay(events, &slow);
+    assert!(ghost.tick(&slow).is_some());
+    clock.advance(400);
+    assert!(ghost.tick(&slow).is_none());
+    clock.advance(400);
+    assert!(ghost.tick(&slow).is_some());
+
+    // Changing speed carries on from the current reading
+    let before = slow.now_ms();
+    slow.set_speed(2.0);
+    assert_eq!(slow.now_ms(), before);
+    clock.advance(100);
+    assert_eq!(slow.now_ms(), before + 200);
+}
+
+#[test]
+fn test_paused_time_is_excluded_and_recorded() {
+    let quotes = vec![MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "abc".to_string(),
+    }];
+    let mut game = GameState::new(quotes.clone());
+    game.start_race_at(&crate::RaceConfig::default(), 0);
+    game.type_char('a', 1_000);
+    game.pause(1_000);
+    // Keys are ignored and the clock stands still while paused
+    game.type_char('b', 5_000);
+    assert_eq!(game.current_char, 1);
+    game.tick(9_000);
+    assert_eq!(game.elapsed_at(9_000), 1.0);
+    game.resume(11_000);
+    game.type_char('b', 11_500);
+    game.tick(12_000);
+    game.type_char('c', 12_000);
+    assert_eq!(game.status, GameStatus::Finished);
+    assert_eq!(game.elapsed, 2.0);
+
+    let replay = game.recorded_replay();
+    assert_eq!(replay.pauses, vec![crate::game::PauseSpan { start_ms: 1_000, end_ms: 11_000 }]);
+    let times: Vec<u64> = replay.active_events().iter().map(|e| e.milliseconds_since_epoch_utc).collect();
+    assert_eq!(times, vec![1_000, 1_500, 2_000]);
+    assert_eq!(crate::inspect::summarize_replay(&quotes, &replay).elapsed, 1.0);
+
+    // A key stamped inside a claimed pause is a forgery
+    let mut forged = replay.clone();
+    forged.events[1].milliseconds_since_epoch_utc = 5_000;
+    assert_eq!(forged.keys_while_paused(), 1);
+    let verdict = crate::verify::verify_replay(&quotes, &forged, &crate::verify::ExpectedRace::default(), None);
+    assert!(verdict.suspicions.contains(&crate::verify::Suspicion::KeysWhilePaused { keys: 1 }));
+}
+
+#[test]
+fn test_keybindings_rebind_and_persist() {
+    use crate::keyboard::shortcuts::{load_keybindings, matching_commands, save_keybindings, Chord, Command, Keybindings};
+    let mut keys = Keybindings::default();
+    assert_eq!(keys.command_for(&Chord::ctrl("R"), true), Some(Command::Restart));
+    assert_eq!(keys.command_for(&Chord::new("ESC"), true), Some(Command::Pause));
+
+    // A bare letter is typing mid-race, but works from the menus
+    assert!(keys.rebind(Command::ToggleGhost, Chord::new("G")));
+    assert_eq!(keys.command_for(&Chord::new("G"), true), None);
+    assert_eq!(keys.command_for(&Chord::new("G"), false), Some(Command::ToggleGhost));
+    // Two commands can't share a chord
+    assert!(!keys.rebind(Command::NextQuote, Chord::ctrl("R")));
+    assert_eq!(keys.chord(Command::NextQuote), Some(&Chord::ctrl("N")));
+
+    let chord = Chord { shift: true, ..Chord::ctrl("P") };
+    assert_eq!(chord.to_string(), "Ctrl+Shift+P");
+    assert_eq!(Chord::parse("ctrl+shift+p"), Some(chord));
+    assert_eq!(Chord::parse("Hyper+P"), None);
+
+    let mut storage = crate::storage::MemoryStorage::default();
+    assert!(save_keybindings(&mut storage, &keys));
+    assert_eq!(load_keybindings(&storage), keys);
+    assert_eq!(matching_commands("ghost"), vec![Command::ToggleGhost, Command::SaveGhost]);
+}
+
+#[test]
+fn test_input_chain_and_game_events() {
+    use crate::events::{EventBus, GameEvent, GameEventHandler};
+    use crate::keyboard::dispatch::{dispatch, TypingLayer};
+    use crate::keyboard::event::{KeyboardEvent, KeyboardEventHandler, Modifiers};
+    use crate::keyboard::shortcuts::{Command, Keybindings, ShortcutLayer};
+    use crate::ui::{CommandPaletteState, KeyboardDisplayState};
+    let quotes = vec![MeditationQuote {
+        original_quotes: vec![],
+        expanded_meditation: "to be".to_string(),
+    }];
+    let mut game = GameState::new(quotes);
+    let mut opponent = GameState::default();
+    game.start_race_at(&crate::RaceConfig::default(), 0);
+    let keybindings = Keybindings::default();
+    let mut display = KeyboardDisplayState::default();
+    let press = |key: &str, ctrl: bool| KeyboardEvent::KeyPress {
+        key: key.to_string(),
+        modifiers: Modifiers { ctrl, ..Modifiers::default() },
+        repeat: false,
+    };
+    let text = |t: &str| KeyboardEvent::Text { text: t.to_string() };
+
+    let mut send = |event: KeyboardEvent, palette_open: bool, game: &mut GameState| {
+        let mut shortcuts = ShortcutLayer::new(&keybindings, true, palette_open);
+        let mut palette = CommandPaletteState::default();
+        let mut typing = TypingLayer { game, opponent: &mut opponent, split: false, now_ms: 100 };
+        let mut chain: Vec<&mut dyn KeyboardEventHandler> = vec![&mut shortcuts];
+        if palette_open {
+            chain.push(&mut palette);
+        }
+        chain.push(&mut typing);
+        chain.push(&mut display);
+        let handled = dispatch(&event, &mut chain);
+        (handled, shortcuts.fired)
+    };
+    // Shortcuts come first and keep their chord from the race
+    assert_eq!(send(press("R", true), false, &mut game), (true, vec![Command::Restart]));
+    // An open palette swallows typing
+    assert_eq!(send(text("t"), true, &mut game), (true, vec![]));
+    assert_eq!(game.current_char, 0);
+    for t in ["t", "p", "o", " ", "b", "e"] {
+        send(text(t), false, &mut game);
+    }
+    assert_eq!(game.status, GameStatus::Finished);
+    // Letter presses fall through to the key animation
+    send(press("B", false), false, &mut game);
+    assert!(display.pressed.contains("B"));
+
+    #[derive(Default)]
+    struct Recorder(Vec<GameEvent>);
+    impl GameEventHandler for Recorder {
+        fn handle_game_event(&mut self, event: &GameEvent) {
+            self.0.push(event.clone());
+        }
+    }
+    let mut bus = EventBus::default();
+    bus.publish_all(game.take_events());
+    let mut recorder = Recorder::default();
+    bus.deliver(&mut [&mut recorder]);
+    assert_eq!(
+        recorder.0,
+        vec![
+            GameEvent::ErrorMade { index: 1, expected: 'o', typed: 'p' },
+            GameEvent::WordCompleted { word: "to".to_string(), clean: false },
+            GameEvent::WordCompleted { word: "be".to_string(), clean: true },
+        ]
+    );
+    assert!(bus.deliver(&mut [&mut recorder]).is_empty());
+}
+
+#[test]
+fn test_settings_default_dark_and_persist() {
+    use crate::settings::{load_settings, save_settings, GhostChoice, Settings, SETTINGS_KEY};
+    use crate::storage::Storage;
+    let mut storage = crate::storage::MemoryStorage::default();
+    let settings = load_settings(&storage);
+    assert_eq!(settings.theme, "Dark");
+    assert_eq!(settings, Settings::default());
+
+    let changed = Settings {
+        ui_scale: 1.5,
+        layout: crate::keyboard::layout::KeyboardLayout::Dvorak,
+        policy: crate::ErrorPolicy::Forgiving,
+        ghost: GhostChoice::PersonalBest,
+        countdown_secs: 0,
+        ..Settings::default()
+    };
+    assert!(save_settings(&mut storage, &changed));
+    assert_eq!(load_settings(&storage), changed);
+
+    // Missing fields take defaults and out-of-range values are pulled back in
+    storage.put(SETTINGS_KEY, r#"{"ui_scale": 40.0, "theme": "Neon"}"#);
+    let loaded = load_settings(&storage);
+    assert_eq!(loaded.ui_scale, crate::settings::MAX_UI_SCALE);
+    assert_eq!(loaded.theme, "Neon");
+    assert_eq!(loaded.countdown_secs, Settings::default().countdown_secs);
+}
+
+#[test]
+fn test_user_themes_load_beside_built_ins() {
+    use crate::storage::{MemoryStorage, Storage};
+    use crate::theme::{available_themes, find_theme, Theme};
+    let neon = Theme {
+        name: "Neon".to_string(),
+        caret: eframe::egui::Color32::from_rgba_unmultiplied(255, 0, 255, 128),
+        ..Theme::dark()
+    };
+    let json = serde_json::to_string(&neon).unwrap();
+    assert!(json.contains(r##""caret":"#ff00ff80""##));
+    assert!(json.contains(r##""background":"#1b1b20""##));
+
+    let mut storage = MemoryStorage::default();
+    storage.put("themes/neon", &json);
+    // A user theme can't replace a built-in one, and broken files are skipped
+    storage.put("themes/fake", &serde_json::to_string(&Theme { name: "Dark".to_string(), ..Theme::light() }).unwrap());
+    storage.put("themes/broken", r#"{"name": "Broken", "text": "red"}"#);
+    let themes = available_themes(&storage);
+    let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["Dark", "Light", "High contrast", "Solarized", "Neon"]);
+    assert_eq!(find_theme(&themes, "Neon"), neon);
+    assert_eq!(find_theme(&themes, "Dark"), Theme::dark());
+    assert_eq!(find_theme(&themes, "Gone"), Theme::default());
+}
+
+#[test]
+fn test_color_vision_presets_and_cues() {
+    use crate::keyboard::onscreen::weakness_marker;
+    use crate::theme::{ColorVision, Theme};
+    use crate::ui::helper::Marker;
+    let dark = Theme::dark();
+    assert_eq!(dark.clone().adapted(ColorVision::Typical, false), dark);
+    for vision in &ColorVision::ALL[1..] {
+        let adapted = dark.clone().adapted(*vision, true);
+        assert!(adapted.cues);
+        assert_ne!(adapted.incorrect, dark.incorrect, "{:?}", vision);
+        assert_ne!(adapted.incorrect, adapted.corrected);
+        // Only feedback colors change
+        assert_eq!(adapted.background, dark.background);
+        assert_eq!(adapted.correct, dark.correct);
+    }
+    // Cues are a player setting and never saved with a theme
+    let json = serde_json::to_string(&dark.clone().adapted(ColorVision::Typical, true)).unwrap();
+    assert!(!serde_json::from_str::<Theme>(&json).unwrap().cues);
+
+    assert_eq!(weakness_marker(0.0), None);
+    assert_eq!(weakness_marker(0.3), Some(Marker::Dot));
+    assert_eq!(weakness_marker(2.0), Some(Marker::Triangle));
+}
+
+#[test]
+fn test_announcer_words_errors_milestones_and_results() {
+    use crate::a11y::Announcer;
+    use crate::events::{GameEvent, GameEventHandler};
+    let mut announcer = Announcer::default();
+    announcer.set_current_word("quiet");
+    announcer.set_current_word("quiet");
+    // No milestones before the first word is done
+    announcer.observe_wpm(120.0);
+    assert_eq!(announcer.take(), ["quiet"]);
+
+    announcer.handle_game_event(&GameEvent::ErrorMade { index: 5, expected: ' ', typed: 'x' });
+    announcer.handle_game_event(&GameEvent::WordCompleted { word: "quiet".to_string(), clean: false });
+    announcer.set_current_word("mind");
+    announcer.observe_wpm(34.0);
+    announcer.observe_wpm(38.0);
+    announcer.observe_wpm(29.0);
+    announcer.observe_wpm(41.5);
+    announcer.handle_game_event(&GameEvent::RaceFinished { wpm: 41.5, accuracy: 96.2 });
+    assert_eq!(
+        announcer.take(),
+        [
+            "Error, expected space",
+            "mind",
+            "30 words per minute",
+            "40 words per minute",
+            "Race finished. 42 words per minute, 96 percent accuracy",
+        ]
+    );
+
+    announcer.reset();
+    announcer.set_current_word("mind");
+    assert_eq!(announcer.take(), ["mind"]);
+}
+
+#[test]
+fn test_sounds_render_to_clean_buffers() {
+    use crate::audio::{duration_secs, render, Sound, SAMPLE_RATE};
+    for sound in Sound::ALL {
+        let samples = render(sound, SAMPLE_RATE);
+        let expected = (duration_secs(sound) * SAMPLE_RATE as f32).round() as usize;
+        assert!(samples.len().abs_diff(expected) <= 2, "{:?} has {} samples", sound, samples.len());
+        let peak = samples.iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.05 && peak <= 1.0, "{:?} peaks at {}", sound, peak);
+        // Faded at both ends so nothing pops
+        assert!(samples[0].abs() < 0.01 && samples[samples.len() - 1].abs() < 0.01, "{:?}", sound);
+        assert_eq!(render(sound, SAMPLE_RATE), samples);
+    }
+    assert!(duration_secs(Sound::Click) < duration_secs(Sound::Fanfare));
+
+    // The countdown beep is an 880Hz tone: two zero crossings a cycle
+    let beep = render(Sound::CountdownBeep, SAMPLE_RATE);
+    let crossings = beep.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count() as f32;
+    let expected = 2.0 * 880.0 * duration_secs(Sound::CountdownBeep);
+    assert!((crossings - expected).abs() < expected * 0.05, "{} crossings", crossings);
+}
+
+#[test]
+fn test_audio_frame_follows_settings() {
+    use crate::audio::{sound_for, Audio, Sound};
+    use crate::events::{GameEvent, GameEventHandler};
+    use crate::settings::Settings;
+    let mut settings = Settings::default();
+    let mut audio = Audio::default();
+    audio.queue(Sound::Click);
+    audio.queue(Sound::Click);
+    audio.handle_game_event(&GameEvent::WordCompleted { word: "calm".to_string(), clean: true });
+    audio.handle_game_event(&GameEvent::GhostOvertook { player_ahead: true });
+    assert_eq!(audio.take_frame(&settings), [Sound::Click, Sound::Whoosh]);
+
+    // A wrong key buzzes instead of clicking
+    audio.queue(Sound::Click);
+    audio.handle_game_event(&GameEvent::ErrorMade { index: 0, expected: 'a', typed: 's' });
+    assert_eq!(audio.take_frame(&settings), [Sound::ErrorBuzz]);
+
+    settings.muted_sounds.insert(Sound::Fanfare);
+    audio.queue(Sound::CountdownBeep);
+    audio.queue(Sound::Fanfare);
+    assert_eq!(audio.take_frame(&settings), [Sound::CountdownBeep]);
+
+    settings.sound = false;
+    audio.queue(Sound::Click);
+    assert!(audio.take_frame(&settings).is_empty());
+    assert_eq!(sound_for(&GameEvent::RaceFinished { wpm: 50.0, accuracy: 99.0 }), Some(Sound::Fanfare));
+    assert_eq!(sound_for(&GameEvent::GhostOvertook { player_ahead: false }), None);
+}
+
+#[test]
+fn test_race_with_unknown_quote_is_rejected() {
+    use crate::quote_loader::{load_expanded_meditations_from, parse_expanded_meditations, DEFAULT_QUOTES_PATH};
+    use crate::RaceConfig;
+    // The browser build parses the same pack the server counts quotes in
+    let bundled = parse_expanded_meditations(include_str!("../level/expanded-meditations.json"));
+    assert!(!bundled.is_empty());
+    assert_eq!(bundled.len(), load_expanded_meditations_from(DEFAULT_QUOTES_PATH).len());
+
+    let mut game = GameState { quotes: bundled.clone(), status: GameStatus::NotStarted, ..GameState::default() };
+    let config = RaceConfig { quote_index: bundled.len(), seed: "s".to_string(), ..RaceConfig::default() };
+    assert!(!game.start_race_at(&config, 0));
+    assert_eq!(game.status, GameStatus::NotStarted);
+    assert!(game.target_text().is_empty());
+    assert!(game.start_race_at(&RaceConfig { quote_index: 0, ..config }, 0));
+    assert_eq!(game.target_text(), bundled[0].expanded_meditation);
+}
+
+#[test]
+fn test_failed_connect_reports_without_blocking() {
+    use crate::net::OnlineSession;
+    use std::net::TcpListener;
+    use std::time::{Duration, Instant};
+    // A port nobody is listening on
+    let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let mut session = OnlineSession::default();
+    assert!(session.connect(&format!("ws://{}", addr), "Ann"));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while session.last_error.is_none() && Instant::now() < deadline {
+        session.poll();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let error = session.last_error.clone().unwrap_or_default();
+    assert!(error.starts_with("Could not connect to"), "{}", error);
+    assert!(!session.is_connected());
+}
+
+#[test]
+fn test_profile_settings_migrate_and_round_trip() {
+    use crate::keyboard::layout::KeyboardLayout;
+    use crate::profile::Profile;
+    use crate::settings::Settings;
+    // Saved before settings moved out of profiles
+    let old = r#"{"name": "Ann", "preferred_layout": "Dvorak",
+        "settings": {"ui_scale": 1.25, "dark_mode": false, "show_ghost": false, "show_keyboard": true}}"#;
+    let profile: Profile = serde_json::from_str(old).unwrap();
+    let settings = profile.settings.clone().unwrap();
+    assert_eq!(settings.layout, KeyboardLayout::Dvorak);
+    assert_eq!(settings.theme, "Light");
+    assert_eq!(settings.ui_scale, 1.25);
+    assert!(!settings.show_ghost);
+    assert_eq!(settings.countdown_secs, Settings::default().countdown_secs);
+
+    let json = serde_json::to_string(&profile).unwrap();
+    assert_eq!(serde_json::from_str::<Profile>(&json).unwrap(), profile);
+
+    // A profile that never had preferences follows the machine's settings
+    let plain: Profile = serde_json::from_str(r#"{"name": "Bo"}"#).unwrap();
+    assert_eq!(plain, Profile::new("Bo"));
+    let json = serde_json::to_string(&plain).unwrap();
+    assert_eq!(serde_json::from_str::<Profile>(&json).unwrap().settings, None);
+}
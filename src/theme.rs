@@ -0,0 +1,309 @@
+// --- Themes: the colors every renderer draws with ---
+//
+// The active theme is installed into the egui context, where renderers pick
+// it up with `Theme::current`. Besides the built-in themes, players can drop
+// their own into the "themes" storage namespace as JSON, with colors written
+// as "#rrggbb" or "#rrggbbaa".
+use std::sync::Arc;
+
+use eframe::egui;
+use eframe::egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{join_key, Storage};
+
+pub const THEMES_NAMESPACE: &str = "themes";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    /// Start from egui's dark visuals rather than its light ones
+    pub dark: bool,
+    #[serde(with = "hex_color")]
+    pub background: Color32,
+    #[serde(with = "hex_color")]
+    pub text: Color32,
+    /// Passage text not typed yet
+    #[serde(with = "hex_color")]
+    pub pending: Color32,
+    #[serde(with = "hex_color")]
+    pub correct: Color32,
+    #[serde(with = "hex_color")]
+    pub incorrect: Color32,
+    /// Characters that are right now but were mistyped first
+    #[serde(with = "hex_color")]
+    pub corrected: Color32,
+    #[serde(with = "hex_color")]
+    pub caret: Color32,
+    #[serde(with = "hex_color")]
+    pub ghost: Color32,
+    #[serde(with = "hex_color")]
+    pub key_idle: Color32,
+    #[serde(with = "hex_color")]
+    pub key_pressed: Color32,
+    #[serde(with = "hex_color")]
+    pub road: Color32,
+    #[serde(with = "hex_color")]
+    pub player_car: Color32,
+    #[serde(with = "hex_color")]
+    pub opponent_car: Color32,
+    /// Mark feedback with shapes, strikes and patterns as well as color.
+    /// A player setting rather than part of the theme, see `Theme::adapted`.
+    #[serde(skip)]
+    pub cues: bool,
+}
+
+/// Color vision the feedback colors are chosen for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorVision {
+    #[default]
+    Typical,
+    /// Weak green cones; red and green look alike
+    Deuteranopia,
+    /// Weak red cones; red and green look alike and reds look dark
+    Protanopia,
+    /// Weak blue cones; blue and green, yellow and pink look alike
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub const ALL: [ColorVision; 4] = [
+        ColorVision::Typical,
+        ColorVision::Deuteranopia,
+        ColorVision::Protanopia,
+        ColorVision::Tritanopia,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorVision::Typical => "Typical",
+            ColorVision::Deuteranopia => "Deuteranopia",
+            ColorVision::Protanopia => "Protanopia",
+            ColorVision::Tritanopia => "Tritanopia",
+        }
+    }
+
+    /// Replacement (incorrect, corrected, highlight) colors that stay apart
+    /// for this kind of color vision, from the Okabe-Ito palette
+    fn feedback_colors(&self) -> Option<(Color32, Color32, Color32)> {
+        let orange = Color32::from_rgb(230, 159, 0);
+        let vermillion = Color32::from_rgb(213, 94, 0);
+        let sky_blue = Color32::from_rgb(86, 180, 233);
+        let blue = Color32::from_rgb(0, 114, 178);
+        let bluish_green = Color32::from_rgb(0, 158, 115);
+        match self {
+            ColorVision::Typical => None,
+            ColorVision::Deuteranopia => Some((vermillion, sky_blue, blue)),
+            ColorVision::Protanopia => Some((orange, sky_blue, blue)),
+            ColorVision::Tritanopia => Some((vermillion, bluish_green, bluish_green)),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: "Dark".to_string(),
+            dark: true,
+            background: Color32::from_rgb(27, 27, 32),
+            text: Color32::from_rgb(220, 220, 220),
+            pending: Color32::from_rgb(120, 120, 130),
+            correct: Color32::from_rgb(235, 235, 235),
+            incorrect: Color32::from_rgb(230, 80, 80),
+            corrected: Color32::from_rgb(230, 180, 60),
+            caret: Color32::from_rgb(220, 220, 220),
+            ghost: Color32::from_rgba_unmultiplied(120, 160, 255, 160),
+            key_idle: Color32::from_rgb(60, 60, 70),
+            key_pressed: Color32::from_rgb(100, 180, 255),
+            road: Color32::from_rgb(70, 70, 78),
+            player_car: Color32::from_rgb(230, 90, 70),
+            opponent_car: Color32::from_rgb(120, 160, 255),
+            cues: false,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            name: "Light".to_string(),
+            dark: false,
+            background: Color32::from_rgb(248, 248, 245),
+            text: Color32::from_rgb(40, 40, 40),
+            pending: Color32::from_rgb(150, 150, 150),
+            correct: Color32::from_rgb(20, 20, 20),
+            incorrect: Color32::from_rgb(200, 40, 40),
+            corrected: Color32::from_rgb(190, 130, 0),
+            caret: Color32::from_rgb(40, 40, 40),
+            ghost: Color32::from_rgba_unmultiplied(40, 90, 220, 160),
+            key_idle: Color32::from_rgb(215, 215, 222),
+            key_pressed: Color32::from_rgb(60, 130, 230),
+            road: Color32::from_rgb(190, 190, 196),
+            player_car: Color32::from_rgb(210, 60, 40),
+            opponent_car: Color32::from_rgb(40, 90, 220),
+            cues: false,
+        }
+    }
+
+    /// Pure black and white with saturated feedback colors
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "High contrast".to_string(),
+            dark: true,
+            background: Color32::BLACK,
+            text: Color32::WHITE,
+            pending: Color32::from_rgb(190, 190, 190),
+            correct: Color32::WHITE,
+            incorrect: Color32::from_rgb(255, 60, 60),
+            corrected: Color32::from_rgb(255, 220, 0),
+            caret: Color32::from_rgb(255, 255, 0),
+            ghost: Color32::from_rgb(0, 200, 255),
+            key_idle: Color32::from_rgb(30, 30, 30),
+            key_pressed: Color32::WHITE,
+            road: Color32::from_rgb(60, 60, 60),
+            player_car: Color32::from_rgb(255, 255, 0),
+            opponent_car: Color32::from_rgb(0, 200, 255),
+            cues: false,
+        }
+    }
+
+    /// Ethan Schoonover's Solarized, dark variant
+    pub fn solarized() -> Self {
+        Self {
+            name: "Solarized".to_string(),
+            dark: true,
+            background: Color32::from_rgb(0x00, 0x2b, 0x36),
+            text: Color32::from_rgb(0x93, 0xa1, 0xa1),
+            pending: Color32::from_rgb(0x58, 0x6e, 0x75),
+            correct: Color32::from_rgb(0xee, 0xe8, 0xd5),
+            incorrect: Color32::from_rgb(0xdc, 0x32, 0x2f),
+            corrected: Color32::from_rgb(0xb5, 0x89, 0x00),
+            caret: Color32::from_rgb(0x2a, 0xa1, 0x98),
+            ghost: Color32::from_rgb(0x6c, 0x71, 0xc4),
+            key_idle: Color32::from_rgb(0x07, 0x36, 0x42),
+            key_pressed: Color32::from_rgb(0x26, 0x8b, 0xd2),
+            road: Color32::from_rgb(0x07, 0x36, 0x42),
+            player_car: Color32::from_rgb(0xcb, 0x4b, 0x16),
+            opponent_car: Color32::from_rgb(0x26, 0x8b, 0xd2),
+            cues: false,
+        }
+    }
+
+    pub fn built_in() -> Vec<Theme> {
+        vec![Theme::dark(), Theme::light(), Theme::high_contrast(), Theme::solarized()]
+    }
+
+    /// This theme with its feedback colors swapped for ones `vision` can
+    /// tell apart, and non-color cues turned on or off
+    pub fn adapted(mut self, vision: ColorVision, cues: bool) -> Theme {
+        if let Some((incorrect, corrected, highlight)) = vision.feedback_colors() {
+            self.incorrect = incorrect;
+            self.corrected = corrected;
+            self.key_pressed = highlight;
+            self.player_car = incorrect;
+            self.opponent_car = corrected;
+        }
+        self.cues = cues;
+        self
+    }
+
+    /// egui's own widgets in this theme's colors
+    pub fn visuals(&self) -> egui::Visuals {
+        let mut visuals = if self.dark { egui::Visuals::dark() } else { egui::Visuals::light() };
+        visuals.panel_fill = self.background;
+        visuals.window_fill = self.background;
+        visuals.extreme_bg_color = self.key_idle;
+        visuals.override_text_color = Some(self.text);
+        visuals.error_fg_color = self.incorrect;
+        visuals.warn_fg_color = self.corrected;
+        visuals.selection.bg_fill = self.key_pressed;
+        visuals.text_cursor.stroke.color = self.caret;
+        visuals
+    }
+
+    /// Make this the theme renderers draw with, and restyle egui to match
+    pub fn install(self, ctx: &egui::Context) {
+        ctx.set_visuals(self.visuals());
+        ctx.data_mut(|d| d.insert_temp(egui::Id::NULL.with("theme"), Arc::new(self)));
+    }
+
+    /// The installed theme, or the default before any is installed
+    pub fn current(ctx: &egui::Context) -> Arc<Theme> {
+        ctx.data(|d| d.get_temp(egui::Id::NULL.with("theme"))).unwrap_or_default()
+    }
+}
+
+/// Black or white, whichever reads better on `background`
+pub fn readable_on(background: Color32) -> Color32 {
+    let [r, g, b, _] = background.to_array();
+    let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    if luma > 140.0 {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
+
+/// Themes saved by the player. Ones that fail to parse are skipped.
+pub fn load_user_themes(storage: &dyn Storage) -> Vec<Theme> {
+    storage
+        .list(THEMES_NAMESPACE)
+        .iter()
+        .filter_map(|name| storage.get(&join_key(THEMES_NAMESPACE, name)))
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect()
+}
+
+/// Built-in themes followed by the player's own, minus any reusing a built-in name
+pub fn available_themes(storage: &dyn Storage) -> Vec<Theme> {
+    let mut themes = Theme::built_in();
+    for theme in load_user_themes(storage) {
+        if !themes.iter().any(|t| t.name == theme.name) {
+            themes.push(theme);
+        }
+    }
+    themes
+}
+
+/// The theme called `name`, falling back to the default
+pub fn find_theme(themes: &[Theme], name: &str) -> Theme {
+    themes.iter().find(|t| t.name == name).cloned().unwrap_or_default()
+}
+
+/// Colors as "#rrggbb", or "#rrggbbaa" when not opaque
+mod hex_color {
+    use eframe::egui::Color32;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color32, serializer: S) -> Result<S::Ok, S::Error> {
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        let hex = if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        };
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color32, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse(&text).ok_or_else(|| serde::de::Error::custom(format!("bad color {:?}", text)))
+    }
+
+    pub fn parse(text: &str) -> Option<Color32> {
+        let hex = text.strip_prefix('#')?;
+        if !hex.is_ascii() {
+            return None;
+        }
+        let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        match hex.len() {
+            6 => Some(Color32::from_rgb(byte(0)?, byte(2)?, byte(4)?)),
+            8 => Some(Color32::from_rgba_unmultiplied(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+            _ => None,
+        }
+    }
+}
@@ -1,54 +1,99 @@
-// --- Time Handling for Cross-Platform ---
+// --- Clocks ---
+//
+// Everything timed reads a `Clock`, so tests can move time by hand and
+// replays can be watched faster or slower than they were typed.
+use std::cell::Cell;
+use std::rc::Rc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::OnceLock;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
 
-/// Returns the current time in seconds since the Unix epoch as f64, cross-platform.
-#[cfg(target_arch = "wasm32")]
-pub fn now_seconds() -> f64 {
-    js_sys::Date::now() / 1000.0
+pub trait Clock {
+    /// Milliseconds since some fixed point; only differences matter
+    fn now_ms(&self) -> u64;
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub fn now_seconds() -> f64 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-    now.as_secs_f64()
+/// The real clock. It is monotonic, so setting the system time or an NTP
+/// correction can't make a race jump or run backwards.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    /// Milliseconds since the first reading in this process
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now_ms(&self) -> u64 {
+        static START: OnceLock<Instant> = OnceLock::new();
+        START.get_or_init(Instant::now).elapsed().as_millis() as u64
+    }
+
+    /// Milliseconds since the page loaded
+    #[cfg(target_arch = "wasm32")]
+    fn now_ms(&self) -> u64 {
+        web_sys::window()
+            .and_then(|w| w.performance())
+            .map(|p| p.now() as u64)
+            .unwrap_or(0)
+    }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub type TimeInstant = std::time::Instant;
+/// Time that only moves when told to. Clones share the same reading.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Rc<Cell<u64>>,
+}
 
-#[cfg(target_arch = "wasm32")]
-#[derive(Debug, Clone)]
-pub struct TimeInstant(pub f64); // Timestamp in milliseconds
+impl ManualClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self { now: Rc::new(Cell::new(start_ms)) }
+    }
+
+    pub fn set(&self, ms: u64) {
+        self.now.set(ms);
+    }
 
-#[cfg(target_arch = "wasm32")]
-impl TimeInstant {
-    pub fn now() -> Self {
-        let window = web_sys::window().expect("no global window exists");
-        let performance = window.performance().expect("performance object not available");
-        Self(performance.now())
+    pub fn advance(&self, ms: u64) {
+        self.now.set(self.now.get() + ms);
     }
-    pub fn elapsed(&self) -> TimeDuration {
-        let now = Self::now();
-        TimeDuration(((now.0 - self.0) / 1000.0) as f32) // Convert ms to seconds
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> u64 {
+        self.now.get()
     }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub struct TimeDuration(pub f32); // Duration in seconds
+/// Another clock running faster or slower; a speed of 0.5 is slow motion
+#[derive(Debug, Clone)]
+pub struct ScaledClock<C: Clock = SystemClock> {
+    inner: C,
+    speed: Cell<f64>,
+    /// Inner and scaled readings when the speed last changed
+    anchor: Cell<(u64, u64)>,
+}
 
-#[cfg(not(target_arch = "wasm32"))]
-impl TimeDuration {
-    pub fn as_secs_f32(&self) -> f32 {
-        self.0
+impl<C: Clock> ScaledClock<C> {
+    pub fn new(inner: C, speed: f64) -> Self {
+        let now = inner.now_ms();
+        Self { inner, speed: Cell::new(speed.max(0.0)), anchor: Cell::new((now, now)) }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed.get()
     }
-}
 
-#[cfg(target_arch = "wasm32")]
-pub struct TimeDuration(pub f32); // Duration in seconds
+    /// Change speed from now on without the reading jumping
+    pub fn set_speed(&self, speed: f64) {
+        let scaled = self.now_ms();
+        self.anchor.set((self.inner.now_ms(), scaled));
+        self.speed.set(speed.max(0.0));
+    }
+}
 
-#[cfg(target_arch = "wasm32")]
-impl TimeDuration {
-    pub fn as_secs_f32(&self) -> f32 {
-        self.0
+impl<C: Clock> Clock for ScaledClock<C> {
+    fn now_ms(&self) -> u64 {
+        let (inner_at, scaled_at) = self.anchor.get();
+        let passed = self.inner.now_ms().saturating_sub(inner_at) as f64;
+        scaled_at + (passed * self.speed.get()) as u64
     }
 }
@@ -1,12 +1,30 @@
 use crate::ui::sections_top::TopSectionState;
 use crate::ui::sections_left::PlayerViewState;
-use crate::ui::sections_keyboard::TextInputState;
+use crate::ui::sections_keyboard::{KeyboardDisplayState, TextInputState};
 use crate::sections::FooterState;
 // use crate::keyboard::KeyboardState; (removed, not found)
 // use crate::keyboard::KeyState; (removed, not found)
 use crate::keyboard::input::TypingBuffer;
 use crate::keyboard::simulator::KeyboardSimulator;
-use crate::game::GameState;
+use crate::keyboard::shortcuts::Keybindings;
+use crate::game::{GameState, RaceConfig};
+use crate::a11y::Announcer;
+use crate::audio::Audio;
+use crate::events::EventBus;
+use crate::profile::ProfileStore;
+use crate::settings::Settings;
+use crate::theme::Theme;
+use crate::leaderboard::Leaderboard;
+use crate::storage::{MemoryStorage, Storage};
+use crate::time::{Clock, SystemClock};
+use crate::ui::sections_leaderboard::LeaderboardViewState;
+use crate::ui::sections_start::StartScreenState;
+use crate::ui::sections_results::ResultsState;
+use crate::ui::sections_stats::StatsViewState;
+use crate::ui::sections_shortcuts::{CommandPaletteState, ShortcutEditorState};
+use crate::ui::sections_settings::SettingsViewState;
+use crate::net::OnlineSession;
+use crate::versus::VersusState;
 
 pub struct TyperRacerApp {
     pub game: GameState,
@@ -16,19 +34,61 @@ pub struct TyperRacerApp {
     pub player1_view: PlayerViewState,
     pub player2_view: PlayerViewState,
     pub text_input: TextInputState,
-    // pub keyboard_display: KeyboardState, (removed)
+    pub keyboard_display: KeyboardDisplayState,
     pub footer: FooterState,
-    pub ui_scale: f32,
-    pub dark_mode: bool,
-    pub show_ghost: bool,
-    pub show_keyboard: bool,
+    /// Settings in use: the active player's own, or else `machine_settings`
+    pub settings: Settings,
+    /// Settings edited since they were last applied to egui
+    pub settings_changed: bool,
+    pub settings_view: SettingsViewState,
+    /// Settings for this machine, used by players without their own
+    pub machine_settings: Settings,
+    /// Built-in themes and the player's own, in the order offered
+    pub themes: Vec<Theme>,
     pub last_pressed_key: Option<String>,
     pub keyboard_simulator: KeyboardSimulator,
+    pub profiles: ProfileStore,
+    pub start_screen: StartScreenState,
+    pub results: ResultsState,
+    /// The right lane: a replayed ghost fed by `keyboard_simulator`, or a
+    /// second player sharing the keyboard
+    pub opponent: GameState,
+    pub leaderboard: Leaderboard,
+    pub leaderboard_view: LeaderboardViewState,
+    pub show_leaderboard: bool,
+    pub stats_view: StatsViewState,
+    pub show_stats: bool,
+    /// The race to start once the warm-up round in progress is done
+    pub pending_race: Option<RaceConfig>,
+    /// The two-player session in progress, if any
+    pub versus: Option<VersusState>,
+    /// Passage of the second player in a split-keyboard race
+    pub player2_input: TextInputState,
+    pub online: OnlineSession,
+    pub keybindings: Keybindings,
+    pub palette: CommandPaletteState,
+    pub show_palette: bool,
+    pub shortcut_editor: ShortcutEditorState,
+    pub show_settings: bool,
+    /// Resume the race once the overlay that paused it closes
+    pub resume_on_close: bool,
+    /// Game events waiting to reach the sections that subscribe to them
+    pub events: EventBus,
+    /// Tells screen readers about the race
+    pub announcer: Announcer,
+    /// Whether the player led the opponent lane last frame
+    pub player_ahead: Option<bool>,
+    /// A race waiting to start, and the clock reading it starts at
+    pub countdown: Option<(RaceConfig, u64)>,
+    /// Seconds left in the countdown when it last beeped
+    pub countdown_beeped: Option<u64>,
+    pub audio: Audio,
+    /// Where profiles, replays and the leaderboard are saved
+    pub storage: Box<dyn Storage>,
+    /// Times races, ghosts and replays; swap it to test or change speed
+    pub clock: Box<dyn Clock>,
 }
 
-
-
-
 impl Default for TyperRacerApp {
     fn default() -> Self {
         TyperRacerApp {
@@ -39,19 +99,52 @@ impl Default for TyperRacerApp {
                 ..TopSectionState::default()
             },
             player1_view: PlayerViewState::default(),
-            player2_view: PlayerViewState::default(),
+            player2_view: PlayerViewState {
+                opponent: true,
+                ..PlayerViewState::default()
+            },
             text_input: TextInputState {
                 current_quote: String::new(),
                 current_position: 0,
                 typed_text: String::new(),
+                ..TextInputState::default()
             },
+            keyboard_display: KeyboardDisplayState::default(),
             footer: FooterState::default(),
-            ui_scale: 1.0,
-            dark_mode: false,
-            show_ghost: false,
-            show_keyboard: true,
+            settings: Settings::default(),
+            settings_changed: true,
+            settings_view: SettingsViewState::default(),
+            machine_settings: Settings::default(),
+            themes: Theme::built_in(),
             last_pressed_key: None,
             keyboard_simulator: KeyboardSimulator::new(),
+            profiles: ProfileStore::default(),
+            start_screen: StartScreenState::default(),
+            results: ResultsState::default(),
+            opponent: GameState::default(),
+            leaderboard: Leaderboard::default(),
+            leaderboard_view: LeaderboardViewState::default(),
+            show_leaderboard: false,
+            stats_view: StatsViewState::default(),
+            show_stats: false,
+            pending_race: None,
+            versus: None,
+            player2_input: TextInputState::default(),
+            online: OnlineSession::default(),
+            keybindings: Keybindings::default(),
+            palette: CommandPaletteState::default(),
+            show_palette: false,
+            shortcut_editor: ShortcutEditorState::default(),
+            show_settings: false,
+            resume_on_close: false,
+            events: EventBus::default(),
+            announcer: Announcer::default(),
+            player_ahead: None,
+            countdown: None,
+            countdown_beeped: None,
+            audio: Audio::default(),
+            storage: Box::new(MemoryStorage::default()),
+            clock: Box::new(SystemClock),
         }
     }
 }
@@ -1,25 +1,41 @@
 use eframe::egui;
 
-/// Draw a keyboard key with the given label, size, and pressed state
+use crate::settings::reduced_motion;
+use crate::theme::{readable_on, Theme};
+
+/// How long a key takes to light up or fade out
+const KEY_FADE_SECONDS: f32 = 0.12;
+/// How long a car takes to catch up with its progress
+const CAR_GLIDE_SECONDS: f32 = 0.2;
+/// Road markings scroll past this many dash spacings over a whole race
+const ROAD_SCROLL_DASHES: f32 = 40.0;
+const DASH_SPACING: f32 = 16.0;
+
+/// `seconds`, or none at all when the player asked for reduced motion
+pub fn motion_seconds(ctx: &egui::Context, seconds: f32) -> f32 {
+    if reduced_motion(ctx) {
+        0.0
+    } else {
+        seconds
+    }
+}
+
+/// Draw a keyboard key with the given label, size, and pressed state, in
+/// the active theme's key colors
 pub fn draw_key(ui: &mut egui::Ui, label: &str, size_factor: f32, key_size: f32, anim: f32) -> egui::Response {
+    let theme = Theme::current(ui.ctx());
     let key_width = key_size * size_factor;
     let key_height = key_size;
-    // Animation: anim in [0,1], 1 means just pressed, 0 means idle
-    let bg_color = if anim > 0.0 {
-        let t = anim;
-        egui::Color32::from_rgb(
-            (60.0 + (100.0-60.0)*t) as u8,
-            (60.0 + (180.0-60.0)*t) as u8,
-            (70.0 + (255.0-70.0)*t) as u8,
-        )
-    } else {
-        egui::Color32::from_rgb(60, 60, 70)
-    };
-    let text_color = if anim > 0.5 { egui::Color32::BLACK } else { egui::Color32::WHITE };
     let (rect, response) = ui.allocate_exact_size(
         egui::vec2(key_width, key_height),
         egui::Sense::click()
     );
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, true, label));
+    // Animation: anim in [0,1], 1 means just pressed, 0 means idle
+    let fade = motion_seconds(ui.ctx(), KEY_FADE_SECONDS);
+    let anim = ui.ctx().animate_value_with_time(response.id.with("glow"), anim.clamp(0.0, 1.0), fade);
+    let bg_color = theme.key_idle.lerp_to_gamma(theme.key_pressed, anim);
+    let text_color = readable_on(bg_color);
     ui.painter().rect_filled(rect, 4.0, bg_color);
     ui.painter().text(
         rect.center(),
@@ -31,12 +47,121 @@ pub fn draw_key(ui: &mut egui::Ui, label: &str, size_factor: f32, key_size: f32,
     response
 }
 
-/// FNV-1a hash for deterministic seed-to-number
-pub fn fnv_hash(s: &str) -> u64 {
-    let mut hash: u64 = 0xcbf29ce484222325;
-    for b in s.as_bytes() {
-        hash ^= *b as u64;
-        hash = hash.wrapping_mul(0x100000001b3);
+/// A strip of road with a car at `progress` (0.0 to 1.0) along it. The
+/// markings scroll back as the car moves, unless motion is reduced.
+pub fn draw_road(ui: &mut egui::Ui, label: &str, progress: f32, car_color: egui::Color32) -> egui::Response {
+    let theme = Theme::current(ui.ctx());
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 18.0), egui::Sense::hover());
+    response.widget_info(|| egui::WidgetInfo {
+        value: Some(f64::from(progress.clamp(0.0, 1.0)) * 100.0),
+        ..egui::WidgetInfo::labeled(egui::WidgetType::ProgressIndicator, true, label)
+    });
+    let reduced = reduced_motion(ui.ctx());
+    let progress = ui
+        .ctx()
+        .animate_value_with_time(response.id.with("car"), progress, motion_seconds(ui.ctx(), CAR_GLIDE_SECONDS));
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 3.0, theme.road);
+    let scroll = if reduced { 0.0 } else { (progress * ROAD_SCROLL_DASHES).fract() * DASH_SPACING };
+    let dash = egui::Stroke::new(1.5, theme.road.lerp_to_gamma(theme.text, 0.35));
+    let mut x = rect.left() - scroll;
+    while x < rect.right() {
+        painter.line_segment([egui::pos2(x, rect.center().y), egui::pos2(x + DASH_SPACING / 2.0, rect.center().y)], dash);
+        x += DASH_SPACING;
     }
-    hash
+    let car_width = 24.0_f32.min(rect.width());
+    let x = rect.left() + (rect.width() - car_width) * progress.clamp(0.0, 1.0);
+    let car = egui::Rect::from_min_size(egui::pos2(x, rect.top() + 3.0), egui::vec2(car_width, rect.height() - 6.0));
+    painter.rect_filled(car, 3.0, car_color);
+    response
 }
+
+/// A shape drawn on a key so its meaning doesn't rest on color alone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    Dot,
+    Triangle,
+}
+
+fn draw_marker(painter: &egui::Painter, center: egui::Pos2, radius: f32, marker: Marker, color: egui::Color32) {
+    match marker {
+        Marker::Dot => {
+            painter.circle_filled(center, radius, color);
+        }
+        Marker::Triangle => {
+            let points = vec![
+                center + egui::vec2(0.0, -radius),
+                center + egui::vec2(radius, radius),
+                center + egui::vec2(-radius, radius),
+            ];
+            painter.add(egui::Shape::convex_polygon(points, color, egui::Stroke::NONE));
+        }
+    }
+}
+
+/// Draw a key tinted toward the theme's error color by `heat` (0.0 to 1.0),
+/// with `marker` in its corner when the theme asks for shape cues
+pub fn draw_heat_key(ui: &mut egui::Ui, label: &str, size_factor: f32, key_size: f32, heat: f32, marker: Option<Marker>) -> egui::Response {
+    let theme = Theme::current(ui.ctx());
+    let bg_color = theme.key_idle.lerp_to_gamma(theme.incorrect, heat.clamp(0.0, 1.0));
+    let text_color = readable_on(bg_color);
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(key_size * size_factor, key_size), egui::Sense::hover());
+    response.widget_info(|| {
+        let rating = match marker {
+            Some(Marker::Triangle) => ", very weak",
+            Some(Marker::Dot) => ", weak",
+            None => "",
+        };
+        egui::WidgetInfo::labeled(egui::WidgetType::Label, true, format!("{}{}", label, rating))
+    });
+    let painter = ui.painter();
+    painter.rect_filled(rect, 4.0, bg_color);
+    painter.text(rect.center(), egui::Align2::CENTER_CENTER, label, egui::FontId::proportional(14.0), text_color);
+    if let (true, Some(marker)) = (theme.cues, marker) {
+        draw_marker(painter, rect.right_top() + egui::vec2(-6.0, 6.0), 3.5, marker, text_color);
+    }
+    response
+}
+
+/// Vertical bars scaled to the tallest, one per value. Flagged bars are
+/// drawn in the error color, and hatched when the theme asks for pattern cues.
+pub fn draw_bar_chart(ui: &mut egui::Ui, bars: &[(f32, bool)], height: f32) -> egui::Response {
+    let theme = Theme::current(ui.ctx());
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), height), egui::Sense::hover());
+    response.widget_info(|| {
+        let values: Vec<String> = bars
+            .iter()
+            .map(|(v, flagged)| format!("{:.0}{}", v, if *flagged { " flagged" } else { "" }))
+            .collect();
+        egui::WidgetInfo::labeled(egui::WidgetType::Label, true, format!("Chart: {}", values.join(", ")))
+    });
+    let painter = ui.painter();
+    painter.rect_filled(rect, 2.0, theme.key_idle);
+    let max = bars.iter().map(|(v, _)| *v).fold(0.0_f32, f32::max);
+    if bars.is_empty() || max <= 0.0 {
+        return response;
+    }
+    let slot = rect.width() / bars.len() as f32;
+    for (i, (value, flagged)) in bars.iter().enumerate() {
+        let bar_height = rect.height() * (value / max).clamp(0.0, 1.0);
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(rect.left() + slot * i as f32 + 1.0, rect.bottom() - bar_height),
+            egui::pos2(rect.left() + slot * (i + 1) as f32 - 1.0, rect.bottom()),
+        );
+        let color = if *flagged { theme.incorrect } else { theme.key_pressed };
+        painter.rect_filled(bar, 0.0, color);
+        if *flagged && theme.cues {
+            let hatch = painter.with_clip_rect(bar);
+            let stroke = egui::Stroke::new(1.5, readable_on(color));
+            let mut x = bar.left() - bar.height();
+            while x < bar.right() {
+                hatch.line_segment([egui::pos2(x, bar.bottom()), egui::pos2(x + bar.height(), bar.top())], stroke);
+                x += 6.0;
+            }
+        }
+    }
+    response
+}
+
+/// FNV-1a hash for deterministic seed-to-number
+pub use crate::rng::fnv_hash;
@@ -5,6 +5,12 @@ pub mod sections_keyboard;
 pub mod sections_left;
 pub mod sections_right;
 pub mod sections_top;
+pub mod sections_start;
+pub mod sections_results;
+pub mod sections_leaderboard;
+pub mod sections_stats;
+pub mod sections_shortcuts;
+pub mod sections_settings;
 
 pub use helper::*;
 pub use sections_footer::*;
@@ -12,3 +18,9 @@ pub use sections_keyboard::*;
 pub use sections_left::*;
 pub use sections_right::*;
 pub use sections_top::*;
+pub use sections_start::*;
+pub use sections_results::*;
+pub use sections_leaderboard::*;
+pub use sections_stats::*;
+pub use sections_shortcuts::*;
+pub use sections_settings::*;
@@ -19,7 +19,7 @@ pub struct PlayerViewState {
 pub struct FooterState {
     pub wpm_history: Vec<f32>,
     pub accuracy: f32,
-    pub current_mode: String,
+    pub current_mode: crate::mode::GameMode,
 }
 
 use eframe::egui;
@@ -33,7 +33,7 @@ impl TopSectionState {
             ui.separator();
             ui.label(format!("Seed: {}", self.level_seed));
             ui.separator();
-            ui.label(format!("Progress: {:.0}%", self.race_progress_percent * 100.0));
+            ui.label(format!("Progress: {:.0}%", self.race_progress_percent));
         });
     }
 }
@@ -56,16 +56,26 @@ impl PlayerViewState {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum FooterAction {
+    None,
+    ShowLeaderboard,
+}
+
 impl FooterState {
-    pub fn render_footer_section(&self, ui: &mut egui::Ui) {
+    pub fn render_footer_section(&self, ui: &mut egui::Ui) -> FooterAction {
+        let mut action = FooterAction::None;
         ui.horizontal(|ui| {
             ui.label("Footer");
             ui.separator();
-            ui.label(format!("Accuracy: {:.1}%", self.accuracy * 100.0));
+            ui.label(format!("Accuracy: {:.1}%", self.accuracy));
             ui.separator();
             ui.label(format!("Mode: {}", self.current_mode));
             ui.separator();
-            ui.label("Lorem ipsum dolor sit amet.");
+            if ui.button("Leaderboard").clicked() {
+                action = FooterAction::ShowLeaderboard;
+            }
         });
+        action
     }
 }
@@ -1,8 +1,6 @@
 // --- Footer Section UI Encapsulation ---
 use eframe::egui;
 
-use crate::keyboard::event::{KeyboardEvent, KeyboardEventHandler};
-
 #[derive(Default, Debug, Clone)]
 pub struct FooterSectionState {
     pub wpm_history: Vec<f32>,
@@ -15,7 +13,7 @@ impl FooterSectionState {
         ui.horizontal(|ui| {
             ui.label("Footer");
             ui.separator();
-            ui.label(format!("Accuracy: {:.1}%", self.accuracy * 100.0));
+            ui.label(format!("Accuracy: {:.1}%", self.accuracy));
             ui.separator();
             ui.label(format!("Mode: {}", self.current_mode));
             ui.separator();
@@ -2,29 +2,25 @@
 use eframe::egui;
 use crate::keyboard::event::{KeyboardEvent, KeyboardEventHandler};
 
-// Add your KeyboardDisplayState struct and impl here
+/// The on-screen keyboard's animation state, fed by the end of the input chain
 #[derive(Default, Debug, Clone)]
 pub struct KeyboardDisplayState {
-    // Add your state fields here
+    /// Keys held down right now, named as in `KeyboardEvent`
+    pub pressed: std::collections::BTreeSet<String>,
 }
 
-
-    // Add your state fields here
-
 impl KeyboardEventHandler for KeyboardDisplayState {
-
     fn handle_keyboard_event(&mut self, event: &KeyboardEvent) -> bool {
         match event {
-            KeyboardEvent::KeyPress { key } => {
-                if key == "K" {
-                    // Example: toggle some display state
-                    // self.show_keyboard = !self.show_keyboard;
-                    return true;
-                }
+            KeyboardEvent::KeyPress { key, .. } => {
+                self.pressed.insert(key.clone());
             }
-            _ => {}
+            KeyboardEvent::KeyRelease { key } => {
+                self.pressed.remove(key);
+            }
+            KeyboardEvent::Text { .. } => return false,
         }
-        false
+        true
     }
 }
 
@@ -34,9 +30,28 @@ pub struct TextInputState {
     pub current_quote: String,
     pub current_position: usize,
     pub typed_text: String,
+    /// Positions mistyped at least once, drawn as corrected once fixed
+    pub mistakes: std::collections::BTreeSet<usize>,
+    /// Characters typed past the end of words, by the position of their space
+    pub extras: std::collections::BTreeMap<usize, String>,
+    /// Where the ghost is in the same passage, when its caret is shown
+    pub ghost_position: Option<usize>,
     // Add your state fields here
 }
 
+impl TextInputState {
+    pub fn render(&self, ui: &mut egui::Ui) {
+        crate::keyboard::display::render_typing_area(
+            ui,
+            &self.current_quote,
+            &self.typed_text,
+            &self.mistakes,
+            &self.extras,
+            self.ghost_position,
+        );
+    }
+}
+
 impl KeyboardEventHandler for TextInputState {
     fn handle_keyboard_event(&mut self, _event: &crate::keyboard::event::KeyboardEvent) -> bool {
         // Text input section does not handle keyboard events by default
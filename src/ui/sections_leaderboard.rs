@@ -0,0 +1,95 @@
+// --- Leaderboard Screen ---
+use eframe::egui;
+
+use crate::game::RaceConfig;
+use crate::leaderboard::{Leaderboard, LeaderboardFilter, MAX_ENTRIES};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LeaderboardAction {
+    None,
+    Close,
+    /// Race the stored replay as a ghost on its quote, seed and mode
+    RaceGhost {
+        replay_ref: String,
+        config: RaceConfig,
+    },
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct LeaderboardViewState {
+    pub filter: LeaderboardFilter,
+    /// Quote the player was last racing, offered as a quick filter
+    pub current_quote: Option<usize>,
+}
+
+impl LeaderboardViewState {
+    pub fn render(&mut self, ui: &mut egui::Ui, board: &Leaderboard) -> LeaderboardAction {
+        let mut action = LeaderboardAction::None;
+        ui.horizontal(|ui| {
+            let pack_text = self.filter.pack.clone().unwrap_or_else(|| "All packs".to_string());
+            egui::ComboBox::from_id_salt("leaderboard_pack")
+                .selected_text(pack_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter.pack, None, "All packs");
+                    for pack in board.packs() {
+                        ui.selectable_value(&mut self.filter.pack, Some(pack.clone()), pack);
+                    }
+                });
+            let mode_text = self.filter.mode.map(|m| m.label()).unwrap_or_else(|| "All modes".to_string());
+            egui::ComboBox::from_id_salt("leaderboard_mode")
+                .selected_text(mode_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter.mode, None, "All modes");
+                    for mode in board.modes() {
+                        ui.selectable_value(&mut self.filter.mode, Some(mode), mode.label());
+                    }
+                });
+            ui.selectable_value(&mut self.filter.quote_index, None, "All quotes");
+            if let Some(quote) = self.current_quote {
+                ui.selectable_value(
+                    &mut self.filter.quote_index,
+                    Some(quote),
+                    format!("Quote #{}", quote),
+                );
+            }
+        });
+        ui.separator();
+        let ranked = board.ranked(&self.filter);
+        if ranked.is_empty() {
+            ui.label("No races recorded yet.");
+        }
+        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            egui::Grid::new("leaderboard_grid").striped(true).show(ui, |ui| {
+                ui.strong("#");
+                ui.strong("Player");
+                ui.strong("WPM");
+                ui.strong("Accuracy");
+                ui.strong("Quote");
+                ui.strong("Mode");
+                ui.strong("Date");
+                ui.end_row();
+                for (rank, entry) in ranked.iter().take(MAX_ENTRIES).enumerate() {
+                    ui.label(format!("{}", rank + 1));
+                    ui.label(&entry.player);
+                    ui.label(format!("{:.1}", entry.wpm));
+                    ui.label(format!("{:.1}%", entry.accuracy));
+                    ui.label(format!("#{}", entry.quote_index));
+                    ui.label(entry.mode.label());
+                    ui.label(entry.date.format("%Y-%m-%d %H:%M").to_string());
+                    if ui.button("Race Ghost").clicked() {
+                        action = LeaderboardAction::RaceGhost {
+                            replay_ref: entry.replay_ref.clone(),
+                            config: entry.config(),
+                        };
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        ui.separator();
+        if ui.button("Close").clicked() {
+            action = LeaderboardAction::Close;
+        }
+        action
+    }
+}
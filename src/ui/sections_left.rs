@@ -9,6 +9,9 @@ pub struct LeftSectionState {
     pub boosts: usize,      // Number of speed boosts earned
 }
 
+use crate::events::{GameEvent, GameEventHandler};
+use crate::theme::Theme;
+use crate::ui::helper::draw_road;
 use crate::keyboard::event::KeyboardEventHandler;
 
 // KeyboardEventHandler implementation for PlayerViewState
@@ -18,6 +21,16 @@ pub struct PlayerViewState {
     pub speed: f32,         // Current typing speed
     pub errors: usize,      // Number of typing errors
     pub boosts: usize,      // Number of speed boosts earned
+    pub words: usize,       // Words typed so far
+    pub opponent: bool,     // Drawn in the opponent's car color
+}
+
+impl GameEventHandler for PlayerViewState {
+    fn handle_game_event(&mut self, event: &GameEvent) {
+        if let GameEvent::WordCompleted { .. } = event {
+            self.words += 1;
+        }
+    }
 }
 
 impl KeyboardEventHandler for PlayerViewState {
@@ -26,6 +39,21 @@ impl KeyboardEventHandler for PlayerViewState {
         false
     }
 }
+impl PlayerViewState {
+    pub fn render(&self, ui: &mut egui::Ui, title: &str) {
+        ui.vertical(|ui| {
+            ui.heading(title);
+            let theme = Theme::current(ui.ctx());
+            let car = if self.opponent { theme.opponent_car } else { theme.player_car };
+            draw_road(ui, &format!("{} progress", title), self.car_position, car);
+            ui.label(format!("Speed: {:.1}", self.speed));
+            ui.label(format!("Errors: {}", self.errors));
+            ui.label(format!("Words: {}", self.words));
+            ui.label(format!("Boosts: {}", self.boosts));
+        });
+    }
+}
+
 impl LeftSectionState {
     pub fn render(&self, ui: &mut egui::Ui) {
         ui.vertical(|ui| {
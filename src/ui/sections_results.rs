@@ -0,0 +1,141 @@
+// --- Results Screen shown after a race finishes ---
+use eframe::egui;
+
+use crate::curriculum::LessonStage;
+use crate::net::Standing;
+use crate::versus::{HeadToHead, PlayerResult};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResultsAction {
+    None,
+    RaceAgain,
+    /// Race the run that just finished as a ghost
+    RaceGhost,
+    /// Hand a hot-seat race over to the second player
+    NextPlayer,
+    BackToStart,
+    ShowLeaderboard,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct ResultsState {
+    pub wpm: f32,
+    pub accuracy: f32,
+    pub errors: usize,
+    pub elapsed: f32,
+    pub seed: String,
+    /// The lesson stage just attempted and whether it is now passed
+    pub lesson: Option<(LessonStage, bool)>,
+    pub head_to_head: Option<HeadToHead>,
+    /// The hot-seat player who races next
+    pub next_player: Option<String>,
+    /// Final order of an online race, once everyone is done
+    pub standings: Vec<Standing>,
+    /// Name the run was kept under with the save ghost shortcut
+    pub saved_ghost: Option<String>,
+}
+
+impl ResultsState {
+    pub fn render(&self, ui: &mut egui::Ui) -> ResultsAction {
+        let mut action = ResultsAction::None;
+        ui.vertical_centered(|ui| {
+            ui.heading("Race Finished!");
+            ui.add_space(10.0);
+            ui.label(format!("WPM: {:.1}", self.wpm));
+            ui.label(format!("Accuracy: {:.1}%", self.accuracy));
+            ui.label(format!("Errors: {}", self.errors));
+            ui.label(format!("Time: {:.1}s", self.elapsed));
+            ui.label(format!("Seed: {}", self.seed));
+            if let Some(name) = &self.saved_ghost {
+                ui.weak(format!("Saved as ghost {}", name));
+            }
+            if let Some((stage, passed)) = self.lesson {
+                ui.add_space(10.0);
+                if passed {
+                    ui.strong(format!("{} passed!", stage.label()));
+                } else {
+                    ui.label(format!(
+                        "{} needs {:.0} WPM at {:.0}% accuracy - try again",
+                        stage.label(),
+                        stage.target_wpm(),
+                        stage.target_accuracy()
+                    ));
+                }
+            }
+            if let Some(head_to_head) = &self.head_to_head {
+                ui.add_space(10.0);
+                render_head_to_head(ui, head_to_head);
+            }
+            if !self.standings.is_empty() {
+                ui.add_space(10.0);
+                ui.strong("Online results");
+                for (place, standing) in self.standings.iter().enumerate() {
+                    if standing.finished {
+                        ui.label(format!(
+                            "{}. {} - {:.1} WPM, {:.1}%, {:.1}s",
+                            place + 1,
+                            standing.name,
+                            standing.wpm,
+                            standing.accuracy,
+                            standing.elapsed
+                        ));
+                    } else {
+                        ui.label(format!("-. {} - did not finish", standing.name));
+                    }
+                }
+            }
+            ui.add_space(20.0);
+            if let Some(next) = &self.next_player {
+                if ui.button(format!("{}'s turn", next)).clicked() {
+                    action = ResultsAction::NextPlayer;
+                }
+                return;
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Race Again").clicked() {
+                    action = ResultsAction::RaceAgain;
+                }
+                if ui.button("Race Ghost").clicked() {
+                    action = ResultsAction::RaceGhost;
+                }
+                if ui.button("Leaderboard").clicked() {
+                    action = ResultsAction::ShowLeaderboard;
+                }
+                if ui.button("Change Player").clicked() {
+                    action = ResultsAction::BackToStart;
+                }
+            });
+        });
+        action
+    }
+}
+
+fn render_head_to_head(ui: &mut egui::Ui, head_to_head: &HeadToHead) {
+    match head_to_head.winner() {
+        Some(winner) => ui.heading(format!("{} wins!", head_to_head.players[winner].name)),
+        None => ui.heading("It's a draw!"),
+    };
+    egui::Grid::new("head_to_head_grid").striped(true).show(ui, |ui| {
+        ui.label("");
+        for player in &head_to_head.players {
+            ui.strong(&player.name);
+        }
+        ui.end_row();
+        let [a, b] = &head_to_head.players;
+        let finished = |p: &PlayerResult| if p.finished { "Yes" } else { "No" }.to_string();
+        let rows = [
+            ("WPM", [format!("{:.1}", a.wpm), format!("{:.1}", b.wpm)]),
+            ("Accuracy", [format!("{:.1}%", a.accuracy), format!("{:.1}%", b.accuracy)]),
+            ("Errors", [a.errors.to_string(), b.errors.to_string()]),
+            ("Time", [format!("{:.1}s", a.elapsed), format!("{:.1}s", b.elapsed)]),
+            ("Finished", [finished(a), finished(b)]),
+        ];
+        for (label, values) in rows {
+            ui.label(label);
+            for value in values {
+                ui.label(value);
+            }
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,141 @@
+// --- Settings Panel ---
+use eframe::egui;
+
+use crate::audio::Sound;
+
+use crate::keyboard::layout::KeyboardLayout;
+use crate::policy::ErrorPolicy;
+use crate::theme::ColorVision;
+use crate::settings::{
+    GhostChoice, Settings, MAX_COUNTDOWN_SECS, MAX_FONT_SIZE, MAX_UI_SCALE, MIN_FONT_SIZE, MIN_UI_SCALE,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsAction {
+    None,
+    /// Something was edited and should be applied and saved
+    Changed,
+    /// Keep these settings for the active player only (true), or go back
+    /// to the machine's (false)
+    JustForProfile(bool),
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct SettingsViewState {}
+
+impl SettingsViewState {
+    /// `themes` names every theme that can be chosen. `profile` is the
+    /// active player and whether `settings` are their own. `sound_problem`
+    /// says why sound can't be heard, if it can't.
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        settings: &mut Settings,
+        themes: &[String],
+        profile: Option<(&str, bool)>,
+        sound_problem: Option<&str>,
+    ) -> SettingsAction {
+        if let Some((name, own)) = profile {
+            let mut just_for_profile = own;
+            if ui
+                .checkbox(&mut just_for_profile, format!("Just for {}", name))
+                .on_hover_text("Keep these settings with this player instead of this machine")
+                .changed()
+            {
+                return SettingsAction::JustForProfile(just_for_profile);
+            }
+        }
+        let before = settings.clone();
+        egui::Grid::new("settings_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Theme");
+            egui::ComboBox::from_id_salt("theme")
+                .selected_text(settings.theme.as_str())
+                .show_ui(ui, |ui| {
+                    for theme in themes {
+                        ui.selectable_value(&mut settings.theme, theme.clone(), theme);
+                    }
+                });
+            ui.end_row();
+            ui.label("Color vision");
+            egui::ComboBox::from_id_salt("color_vision")
+                .selected_text(settings.color_vision.label())
+                .show_ui(ui, |ui| {
+                    for vision in ColorVision::ALL {
+                        ui.selectable_value(&mut settings.color_vision, vision, vision.label());
+                    }
+                });
+            ui.end_row();
+            ui.label("UI scale");
+            ui.add(egui::Slider::new(&mut settings.ui_scale, MIN_UI_SCALE..=MAX_UI_SCALE).step_by(0.05));
+            ui.end_row();
+            ui.label("Font size");
+            ui.add(egui::Slider::new(&mut settings.font_size, MIN_FONT_SIZE..=MAX_FONT_SIZE).step_by(1.0));
+            ui.end_row();
+            ui.label("Keyboard layout");
+            egui::ComboBox::from_id_salt("layout")
+                .selected_text(settings.layout.name())
+                .show_ui(ui, |ui| {
+                    for layout in KeyboardLayout::ALL {
+                        ui.selectable_value(&mut settings.layout, layout, layout.name());
+                    }
+                });
+            ui.end_row();
+            ui.label("On mistakes");
+            egui::ComboBox::from_id_salt("policy")
+                .selected_text(settings.policy.label())
+                .show_ui(ui, |ui| {
+                    for policy in ErrorPolicy::ALL {
+                        ui.selectable_value(&mut settings.policy, policy, policy.label());
+                    }
+                });
+            ui.end_row();
+            ui.label("Ghost");
+            egui::ComboBox::from_id_salt("ghost")
+                .selected_text(settings.ghost.label())
+                .show_ui(ui, |ui| {
+                    for ghost in GhostChoice::ALL {
+                        ui.selectable_value(&mut settings.ghost, ghost, ghost.label());
+                    }
+                });
+            ui.end_row();
+            ui.label("Countdown");
+            ui.add(egui::Slider::new(&mut settings.countdown_secs, 0..=MAX_COUNTDOWN_SECS).suffix(" s"));
+            ui.end_row();
+        });
+        ui.checkbox(&mut settings.cues, "Shape and pattern cues")
+            .on_hover_text("Strike through mistakes and mark weak keys and sloppy races with shapes");
+        ui.checkbox(&mut settings.show_ghost, "Show ghost caret");
+        ui.checkbox(&mut settings.show_keyboard, "Show on-screen keyboard");
+        ui.checkbox(&mut settings.sound, "Sound");
+        if let Some(problem) = sound_problem {
+            ui.colored_label(ui.visuals().warn_fg_color, problem);
+        }
+        ui.add_enabled_ui(settings.sound, |ui| {
+            ui.add(egui::Slider::new(&mut settings.volume, 0.0..=1.0).text("Volume"));
+            ui.horizontal_wrapped(|ui| {
+                for sound in Sound::ALL {
+                    let mut on = !settings.muted_sounds.contains(&sound);
+                    if ui.checkbox(&mut on, sound.label()).changed() {
+                        if on {
+                            settings.muted_sounds.remove(&sound);
+                        } else {
+                            settings.muted_sounds.insert(sound);
+                        }
+                    }
+                }
+            });
+        });
+        ui.checkbox(&mut settings.reduced_motion, "Reduced motion")
+            .on_hover_text("Steady caret, no key fades and a still road");
+        ui.checkbox(&mut settings.screen_reader, "Read announcements aloud")
+            .on_hover_text("Speaks the current word, errors and results in the browser. Desktop screen readers announce them anyway.");
+        if ui.button("Reset to defaults").clicked() {
+            *settings = Settings::default();
+        }
+        if *settings != before {
+            SettingsAction::Changed
+        } else {
+            SettingsAction::None
+        }
+    }
+}
@@ -0,0 +1,118 @@
+// --- Command Palette and Shortcut Editor ---
+use eframe::egui;
+
+use crate::keyboard::event::{KeyboardEvent, KeyboardEventHandler};
+use crate::keyboard::shortcuts::{matching_commands, Chord, Command, Keybindings};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteAction {
+    None,
+    Close,
+    Run(Command),
+}
+
+/// Searchable list of every command with its shortcut
+#[derive(Default, Debug, Clone)]
+pub struct CommandPaletteState {
+    pub query: String,
+}
+
+/// The palette is modal: its search field reads egui directly, and nothing
+/// behind it sees the keys
+impl KeyboardEventHandler for CommandPaletteState {
+    fn handle_keyboard_event(&mut self, _event: &KeyboardEvent) -> bool {
+        true
+    }
+}
+
+impl CommandPaletteState {
+    pub fn render(&mut self, ui: &mut egui::Ui, keybindings: &Keybindings) -> PaletteAction {
+        let mut action = PaletteAction::None;
+        let search = ui.add(egui::TextEdit::singleline(&mut self.query).hint_text("Type a command"));
+        search.request_focus();
+        let matches = matching_commands(&self.query);
+        let (enter, escape) = ui.input(|i| (i.key_pressed(egui::Key::Enter), i.key_pressed(egui::Key::Escape)));
+        if enter {
+            if let Some(&first) = matches.first() {
+                action = PaletteAction::Run(first);
+            }
+        }
+        ui.separator();
+        egui::Grid::new("palette_grid").show(ui, |ui| {
+            for command in matches {
+                if ui.button(command.label()).clicked() {
+                    action = PaletteAction::Run(command);
+                }
+                ui.weak(keybindings.chord(command).map(|c| c.to_string()).unwrap_or_default());
+                ui.end_row();
+            }
+        });
+        if escape {
+            action = PaletteAction::Close;
+        }
+        action
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShortcutsAction {
+    None,
+    Rebind(Command, Chord),
+    ResetDefaults,
+}
+
+/// Lists the bindings and captures a new chord for one of them
+#[derive(Default, Debug, Clone)]
+pub struct ShortcutEditorState {
+    /// The command waiting for its new chord
+    pub capturing: Option<Command>,
+    /// A chord pressed while capturing, waiting for `render` to report it
+    pub captured: Option<(Command, Chord)>,
+    /// Why the last rebind was refused
+    pub message: Option<String>,
+}
+
+/// Modal like the palette; while capturing, the next key press becomes the
+/// new chord
+impl KeyboardEventHandler for ShortcutEditorState {
+    fn handle_keyboard_event(&mut self, event: &KeyboardEvent) -> bool {
+        if let (Some(command), KeyboardEvent::KeyPress { key, modifiers, repeat: false }) = (self.capturing, event) {
+            self.captured = Some((command, Chord::from_press(key, *modifiers)));
+            self.capturing = None;
+        }
+        true
+    }
+}
+
+impl ShortcutEditorState {
+    pub fn render(&mut self, ui: &mut egui::Ui, keybindings: &Keybindings) -> ShortcutsAction {
+        let mut action = match self.captured.take() {
+            Some((command, chord)) => ShortcutsAction::Rebind(command, chord),
+            None => ShortcutsAction::None,
+        };
+        egui::Grid::new("shortcut_grid").striped(true).show(ui, |ui| {
+            for command in Command::ALL {
+                ui.label(command.label());
+                let chord = keybindings.chord(command);
+                if self.capturing == Some(command) {
+                    ui.strong("Press a key...");
+                } else if ui.button(chord.map(|c| c.to_string()).unwrap_or_default()).clicked() {
+                    self.capturing = Some(command);
+                    self.message = None;
+                }
+                if chord.is_some_and(|c| !c.is_safe_while_racing()) {
+                    ui.weak("not while racing");
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(message) = &self.message {
+            ui.colored_label(ui.visuals().error_fg_color, message);
+        }
+        if ui.button("Reset to defaults").clicked() {
+            self.capturing = None;
+            action = ShortcutsAction::ResetDefaults;
+        }
+        action
+    }
+}
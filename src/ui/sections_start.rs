@@ -0,0 +1,192 @@
+// --- Start Screen: profile selection before a race ---
+use eframe::egui;
+
+use crate::curriculum::LessonStage;
+use crate::mode::GameMode;
+use crate::net::{OnlineSession, DEFAULT_SERVER_URL};
+use crate::profile::ProfileStore;
+use crate::versus::VersusMode;
+
+/// What the player asked for on the start screen this frame
+#[derive(Debug, Clone, PartialEq)]
+pub enum StartAction {
+    None,
+    StartRace,
+    StartLesson(LessonStage),
+    ShowStats,
+    ShowSettings,
+    /// Race the active player against `second_player`
+    StartVersus,
+    ConnectOnline,
+    DisconnectOnline,
+    SetReady(bool),
+    /// The profile list changed and should be saved
+    ProfilesChanged,
+}
+
+#[derive(Debug, Clone)]
+pub struct StartScreenState {
+    pub new_profile_name: String,
+    pub mode: GameMode,
+    /// Go straight to the race even when problem words are due
+    pub skip_warm_up: bool,
+    pub versus_mode: VersusMode,
+    pub second_player: Option<String>,
+    pub server_url: String,
+}
+
+impl Default for StartScreenState {
+    fn default() -> Self {
+        Self {
+            new_profile_name: String::new(),
+            mode: GameMode::default(),
+            skip_warm_up: false,
+            versus_mode: VersusMode::default(),
+            second_player: None,
+            server_url: DEFAULT_SERVER_URL.to_string(),
+        }
+    }
+}
+
+impl StartScreenState {
+    pub fn render(&mut self, ui: &mut egui::Ui, profiles: &mut ProfileStore, online: &OnlineSession) -> StartAction {
+        let mut action = StartAction::None;
+        ui.vertical_centered(|ui| {
+            ui.heading("Typer Racer");
+            ui.add_space(10.0);
+            ui.label("Choose a player:");
+            let names: Vec<String> = profiles.profiles.iter().map(|p| p.name.clone()).collect();
+            for name in names {
+                let selected = profiles.active.as_deref() == Some(name.as_str());
+                if ui.selectable_label(selected, &name).clicked() && !selected {
+                    profiles.select(&name);
+                    action = StartAction::ProfilesChanged;
+                }
+            }
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.new_profile_name);
+                if ui.button("New Player").clicked() && profiles.create(&self.new_profile_name) {
+                    self.new_profile_name.clear();
+                    action = StartAction::ProfilesChanged;
+                }
+            });
+            if let Some(profile) = profiles.active_mut() {
+                ui.add_space(10.0);
+                ui.label(format!("Races completed: {}", profile.history.len()));
+                if let Some(best) = profile.best_wpm() {
+                    ui.label(format!("Best WPM: {:.1}", best));
+                }
+                egui::CollapsingHeader::new("Lessons").show(ui, |ui| {
+                    egui::Grid::new("lesson_grid").striped(true).show(ui, |ui| {
+                        for stage in LessonStage::ALL {
+                            let result = profile.lessons.results.get(&stage).copied().unwrap_or_default();
+                            ui.label(stage.label());
+                            ui.label(format!(
+                                "Goal {:.0} WPM, {:.0}%",
+                                stage.target_wpm(),
+                                stage.target_accuracy()
+                            ));
+                            if result.best_wpm > 0.0 {
+                                ui.label(format!("Best {:.1} WPM, {:.1}%", result.best_wpm, result.best_accuracy));
+                            } else {
+                                ui.label("-");
+                            }
+                            let unlocked = profile.lessons.is_unlocked(stage);
+                            let button = if result.passed { "Passed - Retry" } else { "Start" };
+                            if ui.add_enabled(unlocked, egui::Button::new(button)).clicked() {
+                                action = StartAction::StartLesson(stage);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+            ui.add_space(10.0);
+            egui::ComboBox::from_label("Game mode")
+                .selected_text(self.mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in GameMode::all() {
+                        ui.selectable_value(&mut self.mode, mode, mode.label());
+                    }
+                });
+            ui.checkbox(&mut self.skip_warm_up, "Skip warm-up");
+            egui::CollapsingHeader::new("Two players").show(ui, |ui| {
+                egui::ComboBox::from_label("Head-to-head")
+                    .selected_text(self.versus_mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in VersusMode::ALL {
+                            ui.selectable_value(&mut self.versus_mode, mode, mode.label());
+                        }
+                    });
+                if self.versus_mode == VersusMode::SplitKeyboard {
+                    ui.label("Left player corrects with Tab, right player with Backspace");
+                }
+                let others: Vec<String> = profiles
+                    .profiles
+                    .iter()
+                    .map(|p| p.name.clone())
+                    .filter(|name| profiles.active.as_ref() != Some(name))
+                    .collect();
+                if self.second_player.as_ref().is_some_and(|p| !others.contains(p)) {
+                    self.second_player = None;
+                }
+                egui::ComboBox::from_label("Second player")
+                    .selected_text(self.second_player.clone().unwrap_or_else(|| "Choose...".to_string()))
+                    .show_ui(ui, |ui| {
+                        for name in others {
+                            ui.selectable_value(&mut self.second_player, Some(name.clone()), name);
+                        }
+                    });
+                let ready = profiles.active().is_some() && self.second_player.is_some();
+                if ui.add_enabled(ready, egui::Button::new("Start Head-to-Head")).clicked() {
+                    action = StartAction::StartVersus;
+                }
+            });
+            egui::CollapsingHeader::new("Online").show(ui, |ui| {
+                if let Some(error) = &online.last_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                if !online.is_connected() {
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.server_url);
+                        if ui.add_enabled(profiles.active().is_some(), egui::Button::new("Connect")).clicked() {
+                            action = StartAction::ConnectOnline;
+                        }
+                    });
+                    return;
+                }
+                for player in &online.lobby {
+                    let you = if Some(player.id) == online.id { " (you)" } else { "" };
+                    let ready = if player.ready { "ready" } else { "waiting" };
+                    ui.label(format!("{}{} - {}", player.name, you, ready));
+                }
+                ui.horizontal(|ui| {
+                    let ready = online.is_ready();
+                    if ui.button(if ready { "Not Ready" } else { "Ready" }).clicked() {
+                        action = StartAction::SetReady(!ready);
+                    }
+                    if ui.button("Disconnect").clicked() {
+                        action = StartAction::DisconnectOnline;
+                    }
+                });
+            });
+            ui.add_space(20.0);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(profiles.active().is_some(), egui::Button::new("Start Race"))
+                    .clicked()
+                {
+                    action = StartAction::StartRace;
+                }
+                if ui.button("Stats").clicked() {
+                    action = StartAction::ShowStats;
+                }
+                if ui.button("Settings").clicked() {
+                    action = StartAction::ShowSettings;
+                }
+            });
+        });
+        action
+    }
+}
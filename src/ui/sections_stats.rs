@@ -0,0 +1,102 @@
+// --- Stats Screen: weak keys and the review schedule ---
+use chrono::{DateTime, Utc};
+use eframe::egui;
+
+use crate::keyboard::layout::KeyboardLayout;
+use crate::keyboard::onscreen::render_heatmap;
+use crate::profile::Profile;
+use crate::ui::helper::draw_bar_chart;
+
+const WEAK_KEYS_SHOWN: usize = 8;
+/// How many of the latest races the WPM chart shows
+const RACES_CHARTED: usize = 20;
+/// Races below this accuracy are flagged in the chart
+const SLOPPY_ACCURACY: f32 = 95.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatsAction {
+    None,
+    Close,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct StatsViewState {}
+
+/// "now", "in 3h", "in 2d" for a review due date
+fn due_label(due: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let hours = (due - now).num_hours();
+    if due <= now {
+        "now".to_string()
+    } else if hours < 24 {
+        format!("in {}h", hours.max(1))
+    } else {
+        format!("in {}d", hours / 24)
+    }
+}
+
+impl StatsViewState {
+    pub fn render(&mut self, ui: &mut egui::Ui, profile: Option<&Profile>, layout: KeyboardLayout, now: DateTime<Utc>) -> StatsAction {
+        let mut action = StatsAction::None;
+        match profile {
+            None => {
+                ui.label("Choose a player to see their stats.");
+            }
+            Some(profile) => {
+                ui.heading(&profile.name);
+                ui.label(format!("Races completed: {}", profile.history.len()));
+                if !profile.history.is_empty() {
+                    let average = profile.history.iter().map(|r| r.wpm).sum::<f32>() / profile.history.len() as f32;
+                    ui.label(format!("Average WPM: {:.1}", average));
+                }
+                let weak: Vec<String> = profile
+                    .key_stats
+                    .weakest_keys(WEAK_KEYS_SHOWN)
+                    .iter()
+                    .map(|k| if *k == ' ' { "space".to_string() } else { k.to_string() })
+                    .collect();
+                if !weak.is_empty() {
+                    ui.label(format!("Weakest keys: {}", weak.join(" ")));
+                }
+                render_heatmap(ui, layout, &profile.key_stats);
+                if !profile.history.is_empty() {
+                    ui.label(format!("WPM by race, flagged below {:.0}% accuracy", SLOPPY_ACCURACY));
+                    let start = profile.history.len().saturating_sub(RACES_CHARTED);
+                    let bars: Vec<(f32, bool)> = profile.history[start..]
+                        .iter()
+                        .map(|r| (r.wpm, r.accuracy < SLOPPY_ACCURACY))
+                        .collect();
+                    draw_bar_chart(ui, &bars, 60.0);
+                }
+                ui.separator();
+                ui.strong("Problem words");
+                let cards = profile.reviews.upcoming();
+                if cards.is_empty() {
+                    ui.label("No problem words yet.");
+                }
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    egui::Grid::new("review_grid").striped(true).show(ui, |ui| {
+                        ui.strong("Word");
+                        ui.strong("Due");
+                        ui.strong("Interval");
+                        ui.strong("Streak");
+                        ui.strong("Lapses");
+                        ui.end_row();
+                        for card in cards {
+                            ui.label(&card.word);
+                            ui.label(due_label(card.due, now));
+                            ui.label(format!("{:.0}d", card.interval_days));
+                            ui.label(card.repetitions.to_string());
+                            ui.label(card.lapses.to_string());
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+        }
+        ui.separator();
+        if ui.button("Close").clicked() {
+            action = StatsAction::Close;
+        }
+        action
+    }
+}
@@ -8,8 +8,11 @@ pub struct TopSectionState {
     pub level_seed: String,
     pub race_progress_percent: f32,
     pub timer_seconds: f32, // Elapsed time in seconds
+    /// Whether the player leads the ghost, once either has pulled ahead
+    pub ghost_lead: Option<bool>,
 }
 
+use crate::events::{GameEvent, GameEventHandler};
 use crate::keyboard::event::KeyboardEventHandler;
 
 impl KeyboardEventHandler for TopSectionState {
@@ -19,6 +22,14 @@ impl KeyboardEventHandler for TopSectionState {
     }
 }
 
+impl GameEventHandler for TopSectionState {
+    fn handle_game_event(&mut self, event: &GameEvent) {
+        if let GameEvent::GhostOvertook { player_ahead } = event {
+            self.ghost_lead = Some(*player_ahead);
+        }
+    }
+}
+
 impl TopSectionState {
     pub fn render(&self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
@@ -30,7 +41,11 @@ impl TopSectionState {
             ui.separator();
             ui.label(format!("Timer: {:.1}s", self.timer_seconds));
             ui.separator();
-            ui.label(format!("Progress: {:.0}%", self.race_progress_percent * 100.0));
+            ui.label(format!("Progress: {:.0}%", self.race_progress_percent));
+            if let Some(ahead) = self.ghost_lead {
+                ui.separator();
+                ui.label(if ahead { "Ahead of the ghost" } else { "Behind the ghost" });
+            }
         });
     }
 }
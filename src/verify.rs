@@ -0,0 +1,179 @@
+// --- Replay verification: recompute a submitted score and flag forgeries ---
+use crate::game::{MeditationQuote, RaceConfig, Replay};
+use crate::inspect::summarize_replay;
+use crate::mode::GameMode;
+
+/// Gaps shorter than this are faster than a person presses keys
+pub const MIN_HUMAN_INTERVAL_MS: u64 = 20;
+/// Share of sub-human gaps a replay may have, allowing for key rollover
+pub const MAX_SUB_HUMAN_SHARE: f32 = 0.25;
+/// Real typing is never this even
+pub const MIN_INTERVAL_STD_DEV_MS: f32 = 5.0;
+/// Consecutive keys within `PASTE_INTERVAL_MS` of each other that count as a paste
+pub const PASTE_BURST_KEYS: usize = 12;
+pub const PASTE_INTERVAL_MS: u64 = 8;
+/// Above the fastest recorded human bursts
+pub const MAX_PLAUSIBLE_WPM: f32 = 250.0;
+/// Allowed difference between a claimed and a recomputed WPM
+pub const WPM_TOLERANCE: f32 = 2.0;
+/// Rhythm checks need at least this many gaps to mean anything
+const MIN_INTERVALS: usize = 10;
+
+/// Something about a replay that a real player could not have produced
+#[derive(Debug, Clone, PartialEq)]
+pub enum Suspicion {
+    /// Timestamps go backwards
+    OutOfOrder,
+    /// The replay is of a different race than the one it was submitted
+    /// for, or its keys were recorded against a different passage
+    WrongPassage,
+    /// Keys were recorded while the race was paused
+    KeysWhilePaused { keys: usize },
+    /// The keys do not finish the race
+    Unfinished,
+    SubHumanIntervals { share: f32 },
+    UniformRhythm { std_dev_ms: f32 },
+    PasteBurst { keys: usize },
+    ImpossibleSpeed { wpm: f32 },
+    ClaimMismatch { claimed_wpm: f32, wpm: f32 },
+}
+
+impl Suspicion {
+    pub fn describe(&self) -> String {
+        match self {
+            Suspicion::OutOfOrder => "key timestamps go backwards".to_string(),
+            Suspicion::WrongPassage => "keys were recorded on a different passage".to_string(),
+            Suspicion::KeysWhilePaused { keys } => format!("{} keys were typed while paused", keys),
+            Suspicion::Unfinished => "the replay does not finish the race".to_string(),
+            Suspicion::SubHumanIntervals { share } => format!(
+                "{:.0}% of keys came less than {} ms apart",
+                share * 100.0,
+                MIN_HUMAN_INTERVAL_MS
+            ),
+            Suspicion::UniformRhythm { std_dev_ms } => {
+                format!("key rhythm is machine-even ({:.1} ms spread)", std_dev_ms)
+            }
+            Suspicion::PasteBurst { keys } => format!("{} keys arrived at paste speed", keys),
+            Suspicion::ImpossibleSpeed { wpm } => format!("{:.0} WPM is beyond human speed", wpm),
+            Suspicion::ClaimMismatch { claimed_wpm, wpm } => {
+                format!("claimed {:.1} WPM but the keys give {:.1}", claimed_wpm, wpm)
+            }
+        }
+    }
+}
+
+/// The race a replay was submitted for. A replay's own config can't be
+/// trusted, since a forger could swap in an easier passage or mode.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExpectedRace {
+    pub quote_index: usize,
+    pub seed: String,
+    pub mode: GameMode,
+}
+
+impl ExpectedRace {
+    pub fn matches(&self, config: &RaceConfig) -> bool {
+        config.quote_index == self.quote_index && config.seed == self.seed && config.mode == self.mode
+    }
+}
+
+/// The score a replay really earns, and what looks forged about it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Verdict {
+    pub wpm: f32,
+    pub accuracy: f32,
+    pub elapsed: f32,
+    pub keystrokes: usize,
+    pub suspicions: Vec<Suspicion>,
+}
+
+impl Verdict {
+    pub fn is_plausible(&self) -> bool {
+        self.suspicions.is_empty()
+    }
+}
+
+/// Check that `replay` is of the `expected` race, then re-run it through the
+/// typing engine and check its timing. The race clock starts at the first
+/// key and stops for recorded pauses. Pass `claimed_wpm` to check a
+/// submitted score.
+pub fn verify_replay(
+    quotes: &[MeditationQuote],
+    replay: &Replay,
+    expected: &ExpectedRace,
+    claimed_wpm: Option<f32>,
+) -> Verdict {
+    let mut suspicions = Vec::new();
+    let summary = summarize_replay(quotes, replay);
+
+    // Gaps are judged on racing time, so pauses do not look like hesitation
+    let intervals: Vec<i64> = replay
+        .active_events()
+        .windows(2)
+        .map(|w| w[1].milliseconds_since_epoch_utc as i64 - w[0].milliseconds_since_epoch_utc as i64)
+        .collect();
+    if intervals.iter().any(|&gap| gap < 0) {
+        suspicions.push(Suspicion::OutOfOrder);
+    }
+    if !expected.matches(&replay.config) || replay.events.iter().any(|e| e.quote_index != expected.quote_index) {
+        suspicions.push(Suspicion::WrongPassage);
+    }
+    let keys = replay.keys_while_paused();
+    if keys > 0 {
+        suspicions.push(Suspicion::KeysWhilePaused { keys });
+    }
+    if !summary.finished {
+        suspicions.push(Suspicion::Unfinished);
+    }
+    let gaps: Vec<u64> = intervals.iter().map(|&gap| gap.max(0) as u64).collect();
+    suspicions.extend(rhythm_suspicions(&gaps));
+
+    let wpm = summary.wpm;
+    if wpm > MAX_PLAUSIBLE_WPM {
+        suspicions.push(Suspicion::ImpossibleSpeed { wpm });
+    }
+    if let Some(claimed_wpm) = claimed_wpm {
+        if claimed_wpm > wpm + WPM_TOLERANCE {
+            suspicions.push(Suspicion::ClaimMismatch { claimed_wpm, wpm });
+        }
+    }
+    Verdict {
+        wpm,
+        accuracy: summary.accuracy,
+        elapsed: summary.elapsed,
+        keystrokes: summary.keystrokes,
+        suspicions,
+    }
+}
+
+/// Checks on the gaps between keys
+fn rhythm_suspicions(gaps: &[u64]) -> Vec<Suspicion> {
+    let mut suspicions = Vec::new();
+    if gaps.len() < MIN_INTERVALS {
+        return suspicions;
+    }
+    let fast = gaps.iter().filter(|&&gap| gap < MIN_HUMAN_INTERVAL_MS).count();
+    let share = fast as f32 / gaps.len() as f32;
+    if share > MAX_SUB_HUMAN_SHARE {
+        suspicions.push(Suspicion::SubHumanIntervals { share });
+    }
+
+    let mean = gaps.iter().sum::<u64>() as f32 / gaps.len() as f32;
+    let variance = gaps.iter().map(|&gap| (gap as f32 - mean).powi(2)).sum::<f32>() / gaps.len() as f32;
+    let std_dev_ms = variance.sqrt();
+    if std_dev_ms < MIN_INTERVAL_STD_DEV_MS {
+        suspicions.push(Suspicion::UniformRhythm { std_dev_ms });
+    }
+
+    // A run of n fast gaps is n + 1 keys
+    let mut longest = 0;
+    let mut run = 0;
+    for &gap in gaps {
+        run = if gap <= PASTE_INTERVAL_MS { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    if longest + 1 >= PASTE_BURST_KEYS {
+        suspicions.push(Suspicion::PasteBurst { keys: longest + 1 });
+    }
+    suspicions
+}
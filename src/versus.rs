@@ -0,0 +1,129 @@
+// --- Two players on one machine ---
+use std::cmp::Ordering;
+
+use crate::curriculum::words_from_letters;
+use crate::game::{GameState, GameStatus, MeditationQuote, RaceConfig};
+use crate::keyboard::layout::KeyboardLayout;
+use crate::mode::GameMode;
+use crate::policy::ErrorPolicy;
+use crate::rng::SeededRng;
+
+/// Words in each player's half of a split-keyboard drill
+pub const SPLIT_DRILL_WORDS: usize = 12;
+
+/// Backspace sits on the right of the keyboard, so in a split-keyboard race
+/// it corrects the right player and the left player corrects with this key
+pub const LEFT_BACKSPACE_KEY: &str = "TAB";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersusMode {
+    /// Players take turns on the same passage; the first run is the
+    /// second player's ghost
+    #[default]
+    HotSeat,
+    /// Both race at once, one on each half of the keyboard
+    SplitKeyboard,
+}
+
+impl VersusMode {
+    pub const ALL: [VersusMode; 2] = [VersusMode::HotSeat, VersusMode::SplitKeyboard];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VersusMode::HotSeat => "Hot-seat",
+            VersusMode::SplitKeyboard => "Split keyboard",
+        }
+    }
+}
+
+/// How one player did in a head-to-head race
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlayerResult {
+    pub name: String,
+    pub wpm: f32,
+    pub accuracy: f32,
+    pub errors: usize,
+    pub elapsed: f32,
+    /// Reached the end of the passage, rather than being stopped by the clock or the other player
+    pub finished: bool,
+}
+
+impl PlayerResult {
+    pub fn from_game(name: &str, game: &GameState) -> Self {
+        Self {
+            name: name.to_string(),
+            wpm: game.wpm(),
+            accuracy: game.accuracy(),
+            errors: game.errors,
+            elapsed: game.elapsed,
+            finished: game.current_char >= game.target_text().chars().count(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeadToHead {
+    pub players: [PlayerResult; 2],
+}
+
+impl HeadToHead {
+    /// Index of the winner, or None for a draw. Finishing beats not
+    /// finishing, then the faster time, then the higher WPM.
+    pub fn winner(&self) -> Option<usize> {
+        let [a, b] = &self.players;
+        let order = b
+            .finished
+            .cmp(&a.finished)
+            .then(a.elapsed.total_cmp(&b.elapsed))
+            .then(b.wpm.total_cmp(&a.wpm));
+        match order {
+            Ordering::Less => Some(0),
+            Ordering::Greater => Some(1),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+/// A two-player session in progress
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VersusState {
+    pub mode: VersusMode,
+    pub players: [String; 2],
+    /// The first player's hot-seat result, once they have raced
+    pub first: Option<PlayerResult>,
+}
+
+/// The (left, right) races of a split-keyboard head-to-head, both under the
+/// player's error policy
+pub fn split_keyboard_configs(seed: &str, layout: KeyboardLayout, policy: ErrorPolicy) -> (RaceConfig, RaceConfig) {
+    let half = |left| RaceConfig {
+        seed: seed.to_string(),
+        mode: GameMode::HalfKeyboard { left },
+        policy,
+        layout,
+        ..RaceConfig::default()
+    };
+    (half(true), half(false))
+}
+
+/// Drill for one half of a split keyboard, using only that hand's letters
+pub fn hand_drill(quotes: &[MeditationQuote], layout: KeyboardLayout, left: bool, seed: &str) -> String {
+    let letters = layout.hand_letters(left).into_iter().collect();
+    let mut rng = SeededRng::from_seed(seed);
+    words_from_letters(quotes, &letters, &mut rng, SPLIT_DRILL_WORDS).join(" ")
+}
+
+/// Which players a key typed in a split-keyboard race goes to, as
+/// (left, right). Space goes to whoever is waiting for one.
+pub fn route_split_key(layout: KeyboardLayout, c: char, left: &GameState, right: &GameState) -> (bool, bool) {
+    let expects_space = |game: &GameState| {
+        game.status == GameStatus::Running && game.target_text().chars().nth(game.current_char) == Some(' ')
+    };
+    if c == ' ' {
+        return (expects_space(left), expects_space(right));
+    }
+    match layout.finger_for(c) {
+        Some(finger) if !finger.is_left_hand() => (false, true),
+        _ => (true, false),
+    }
+}